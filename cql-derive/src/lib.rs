@@ -0,0 +1,143 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, Lit, Meta, NestedMeta, Result};
+
+struct Field {
+    ident: Ident,
+    name: String,
+}
+
+fn rename(field: &syn::Field) -> Result<Option<String>> {
+    let mut name = None;
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("cql")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new_spanned(meta, "expected #[cql(...)]")),
+        };
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => match nv.lit {
+                    Lit::Str(ref s) => name = Some(s.value()),
+                    ref lit => return Err(Error::new_spanned(lit, "expected string literal for rename")),
+                },
+                _ => return Err(Error::new_spanned(nested, "unknown cql attribute")),
+            }
+        }
+    }
+    Ok(name)
+}
+
+fn fields(input: &DeriveInput) -> Result<Vec<Field>> {
+    let named = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => return Err(Error::new_spanned(input, "only structs with named fields are supported")),
+        },
+        _ => return Err(Error::new_spanned(input, "only structs are supported")),
+    };
+
+    named.iter().map(|field| {
+        let ident = field.ident.clone().unwrap();
+        let name = rename(field)?.unwrap_or_else(|| ident.to_string());
+        Ok(Field {
+            ident,
+            name,
+        })
+    }).collect()
+}
+
+fn expand(input: TokenStream, f: fn(&DeriveInput, Vec<Field>) -> TokenStream2) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match fields(&input) {
+        Ok(fields) => f(&input, fields).into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn values(fields: &[Field]) -> TokenStream2 {
+    let idents = fields.iter().map(|f| &f.ident);
+    let names = fields.iter().map(|f| &f.name);
+    quote! {
+        &[#((#names, &self.#idents as &dyn ::cql::types::ToCql)),*]
+    }
+}
+
+#[proc_macro_derive(FromRow, attributes(cql))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    expand(input, |input, fields| {
+        let ident = &input.ident;
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        let idents = fields.iter().map(|f| &f.ident);
+        let names = fields.iter().map(|f| &f.name);
+        quote! {
+            impl #impl_generics ::cql::row::FromRow for #ident #ty_generics #where_clause {
+                fn from_row(
+                    col_specs: &[::cql::response::result::ColSpec],
+                    row: &[::cql::types::Bytes],
+                ) -> ::cql::result::ProtResult<Self> {
+                    Ok(#ident {
+                        #(#idents: ::cql::row::column(col_specs, row, #names)?,)*
+                    })
+                }
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(IntoValues, attributes(cql))]
+pub fn derive_into_values(input: TokenStream) -> TokenStream {
+    expand(input, |input, fields| {
+        let ident = &input.ident;
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        let values = values(&fields);
+        quote! {
+            impl #impl_generics ::cql::row::IntoValues for #ident #ty_generics #where_clause {
+                fn to_values(
+                    &self,
+                    col_specs: &[::cql::response::result::ColSpec],
+                ) -> ::cql::result::ProtResult<Vec<::cql::types::Value>> {
+                    ::cql::row::bind(col_specs, #values)
+                }
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(IntoUdt, attributes(cql))]
+pub fn derive_into_udt(input: TokenStream) -> TokenStream {
+    expand(input, |input, fields| {
+        let ident = &input.ident;
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        let values = values(&fields);
+        quote! {
+            impl #impl_generics ::cql::types::ToCql for #ident #ty_generics #where_clause {
+                fn to_cql(&self, ty: &::cql::types::Opt) -> ::cql::result::ProtResult<::cql::types::Value> {
+                    ::cql::types::udt_to_cql::<Self>(ty, #values)
+                }
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(FromUdt, attributes(cql))]
+pub fn derive_from_udt(input: TokenStream) -> TokenStream {
+    expand(input, |input, fields| {
+        let ident = &input.ident;
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        let idents = fields.iter().map(|f| &f.ident);
+        let names = fields.iter().map(|f| &f.name);
+        quote! {
+            impl #impl_generics ::cql::types::FromCql for #ident #ty_generics #where_clause {
+                fn from_cql(ty: &::cql::types::Opt, bytes: &::cql::types::Bytes) -> ::cql::result::ProtResult<Self> {
+                    let fields = ::cql::types::UdtFields::decode::<Self>(ty, bytes)?;
+                    Ok(#ident {
+                        #(#idents: fields.get(#names)?,)*
+                    })
+                }
+            }
+        }
+    })
+}
@@ -1,3 +1,4 @@
+use crate::def::Version;
 use crate::result::*;
 use crate::types::*;
 
@@ -5,10 +6,68 @@ use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use num_traits::FromPrimitive;
 
 use std::{
-    io,
+    io::{self, Read},
     net::{IpAddr, Ipv4Addr, Ipv6Addr}
 };
 
+pub const DEFAULT_MAX_FRAME_LENGTH: u32 = 256 * 1024 * 1024;
+pub const DEFAULT_MAX_STRING_LENGTH: u32 = 16 * 1024 * 1024;
+pub const DEFAULT_MAX_COLLECTION_COUNT: u32 = 1024 * 1024;
+pub const DEFAULT_MAX_NESTING_DEPTH: u32 = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    max_frame_length: u32,
+    max_string_length: u32,
+    max_collection_count: u32,
+    max_nesting_depth: u32,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            max_string_length: DEFAULT_MAX_STRING_LENGTH,
+            max_collection_count: DEFAULT_MAX_COLLECTION_COUNT,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+        }
+    }
+}
+
+impl Limits {
+    pub fn set_max_frame_length(&mut self, len: u32) {
+        self.max_frame_length = len;
+    }
+
+    pub fn set_max_string_length(&mut self, len: u32) {
+        self.max_string_length = len;
+    }
+
+    pub fn set_max_collection_count(&mut self, count: u32) {
+        self.max_collection_count = count;
+    }
+
+    pub fn set_max_nesting_depth(&mut self, depth: u32) {
+        self.max_nesting_depth = depth;
+    }
+
+    pub fn max_frame_length(&self) -> u32 {
+        self.max_frame_length
+    }
+
+    pub fn max_string_length(&self) -> u32 {
+        self.max_string_length
+    }
+
+    pub fn max_collection_count(&self) -> u32 {
+        self.max_collection_count
+    }
+
+    pub fn max_nesting_depth(&self) -> u32 {
+        self.max_nesting_depth
+    }
+}
+
 pub struct Encoder<B: io::Write> {
     writer: B,
 }
@@ -19,6 +78,7 @@ pub struct Decoder<B: io::Read> {
 
 pub struct Codec<B: io::Write + io::Read> {
     io: B,
+    limits: Limits,
 }
 
 impl<B: io::Write> Encoder<B> {
@@ -45,12 +105,25 @@ impl<B: io::Write + io::Read> Codec<B> {
     pub fn new(io: B) -> Codec<B> {
         Codec {
             io,
+            limits: Limits::default(),
         }
     }
 
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn limits_mut(&mut self) -> &mut Limits {
+        &mut self.limits
+    }
+
     pub fn io(&mut self) -> &mut B {
         &mut self.io
     }
+
+    pub fn io_ref(&self) -> &B {
+        &self.io
+    }
 }
 
 pub trait Encodable<B: io::Write> {
@@ -204,6 +277,13 @@ pub trait Encodable<B: io::Write> {
         self.write_short(v as Short)
     }
 
+    fn write_query_flags(&mut self, v: Int, version: Version) -> ProtResult<()> {
+        match version {
+            Version::V3 | Version::V4 => self.write_byte(v as Byte),
+            Version::V5 => self.write_int(v),
+        }
+    }
+
     fn write_sting_map(&mut self, v: &StringMap) -> ProtResult<()> {
         self.write_short(v.len() as Short)?;
         for (k, v) in v {
@@ -222,6 +302,15 @@ pub trait Encodable<B: io::Write> {
         OK
     }
 
+    fn write_bytes_map(&mut self, v: &BytesMap) -> ProtResult<()> {
+        self.write_short(v.len() as Short)?;
+        for (k, v) in v {
+            self.write_string(k)?;
+            self.write_bytes(v)?;
+        }
+        OK
+    }
+
     fn write_raw_bytes(&mut self, v: &[u8]) -> ProtResult<()> {
         self.io().write_all(v)?;
         OK
@@ -237,6 +326,30 @@ impl<B: io::Write> Encodable<B> for Encoder<B> {
 pub trait Decodable<B: io::Read> {
     fn io(&mut self) -> &mut B;
 
+    fn limits(&self) -> Limits {
+        Limits::default()
+    }
+
+    fn check_count(&self, count: i64) -> ProtResult<()> {
+        if count < 0 {
+            return Err(ProtError::InvalidLength(count));
+        }
+        if count > i64::from(self.limits().max_collection_count()) {
+            return Err(ProtError::LimitExceeded("collection count", count as u64));
+        }
+        OK
+    }
+
+    fn check_string_length(&self, len: i64) -> ProtResult<u32> {
+        if len < 0 {
+            return Err(ProtError::InvalidLength(len));
+        }
+        if len > i64::from(self.limits().max_string_length()) {
+            return Err(ProtError::LimitExceeded("string length", len as u64));
+        }
+        Ok(len as u32)
+    }
+
     fn read_i16(&mut self) -> ProtResult<i16> {
         Ok(self.io().read_i16::<BigEndian>()?)
     }
@@ -263,13 +376,15 @@ pub trait Decodable<B: io::Read> {
 
     fn read_string(&mut self) -> ProtResult<String> {
         let len = self.read_short()?;
-        let bytes = self.read_raw_bytes(len as u32)?;
+        let len = self.check_string_length(i64::from(len))?;
+        let bytes = self.read_raw_bytes(len)?;
         Ok(String::from_utf8(bytes)?)
     }
 
     fn read_long_string(&mut self) -> ProtResult<LongString> {
         let len = self.read_int()?;
-        let bytes = self.read_raw_bytes(len as u32)?;
+        let len = self.check_string_length(i64::from(len))?;
+        let bytes = self.read_raw_bytes(len)?;
         Ok(LongString::from_utf8(bytes)?)
     }
 
@@ -280,6 +395,7 @@ pub trait Decodable<B: io::Read> {
 
     fn read_string_list(&mut self) -> ProtResult<StringList> {
         let len = self.read_short()?;
+        self.check_count(i64::from(len))?;
         let mut list = StringList::new();
         for _ in 1..=len {
             list.push(self.read_string()?);
@@ -306,7 +422,7 @@ pub trait Decodable<B: io::Read> {
         } else if len == -2 {
             Value::NotSet
         } else {
-            unreachable!(len)
+            return Err(ProtError::InvalidLength(i64::from(len)));
         };
         Ok(value)
     }
@@ -317,34 +433,46 @@ pub trait Decodable<B: io::Read> {
     }
 
     fn read_option(&mut self) -> ProtResult<Opt> {
-        let id = FromPrimitive::from_u16(self.read_short()?).unwrap();
-        let value = self.read_option_value(id)?;
+        self.read_nested_option(0)
+    }
+
+    fn read_nested_option(&mut self, depth: u32) -> ProtResult<Opt> {
+        if depth > self.limits().max_nesting_depth() {
+            return Err(ProtError::LimitExceeded("nesting depth", u64::from(depth)));
+        }
+
+        let v = self.read_short()?;
+        let id = FromPrimitive::from_u16(v).ok_or_else(|| ProtError::InvalidEnumValue("OptIds", v.to_string()))?;
+        let value = self.read_option_value(id, depth + 1)?;
         Ok(Opt {
             id,
             value,
         })
     }
 
-    fn read_option_value(&mut self, id: OptIds) -> ProtResult<OptValue> {
+    fn read_option_value(&mut self, id: OptIds, depth: u32) -> ProtResult<OptValue> {
         let value = match id {
             OptIds::Custom => OptValue::Custom(self.read_string()?),
-            OptIds::List => OptValue::List(Box::new(self.read_option()?)),
-            OptIds::Map => OptValue::Map(Box::new(self.read_option()?), Box::new(self.read_option()?)),
-            OptIds::Set => OptValue::Set(Box::new(self.read_option()?)),
-            OptIds::Udt => OptValue::Udt(self.read_option_udt()?),
-            OptIds::Tuple => OptValue::Tuple(self.read_option_tuple()?),
+            OptIds::List => OptValue::List(Box::new(self.read_nested_option(depth)?)),
+            OptIds::Map => {
+                OptValue::Map(Box::new(self.read_nested_option(depth)?), Box::new(self.read_nested_option(depth)?))
+            },
+            OptIds::Set => OptValue::Set(Box::new(self.read_nested_option(depth)?)),
+            OptIds::Udt => OptValue::Udt(self.read_option_udt(depth)?),
+            OptIds::Tuple => OptValue::Tuple(self.read_option_tuple(depth)?),
             _ => OptValue::None,
         };
         Ok(value)
     }
 
-    fn read_option_udt(&mut self) -> ProtResult<OptUdt> {
+    fn read_option_udt(&mut self, depth: u32) -> ProtResult<OptUdt> {
         let ks = self.read_string()?;
         let name = self.read_string()?;
         let len = self.read_short()?;
+        self.check_count(i64::from(len))?;
         let mut fields = Vec::with_capacity(len as usize);
         for _ in 1..=len {
-            fields.push((self.read_string()?, self.read_option()?));
+            fields.push((self.read_string()?, self.read_nested_option(depth)?));
         }
         Ok(OptUdt {
             ks,
@@ -353,11 +481,12 @@ pub trait Decodable<B: io::Read> {
         })
     }
 
-    fn read_option_tuple(&mut self) -> ProtResult<Vec<Opt>> {
+    fn read_option_tuple(&mut self, depth: u32) -> ProtResult<Vec<Opt>> {
         let len = self.read_short()?;
+        self.check_count(i64::from(len))?;
         let mut v = Vec::with_capacity(len as usize);
         for _ in 1..=len {
-            v.push(self.read_option()?);
+            v.push(self.read_nested_option(depth)?);
         }
         Ok(v)
     }
@@ -381,16 +510,25 @@ pub trait Decodable<B: io::Read> {
                 self.io().read_exact(&mut v)?;
                 Ok(IpAddr::V6(Ipv6Addr::from(v)))
             },
-            _ => unreachable!(len),
+            _ => Err(ProtError::InvalidLength(i64::from(len))),
         }
     }
 
     fn read_consistency(&mut self) -> ProtResult<Consistency> {
-        Ok(FromPrimitive::from_u16(self.read_short()?).unwrap())
+        let v = self.read_short()?;
+        FromPrimitive::from_u16(v).ok_or_else(|| ProtError::InvalidEnumValue("Consistency", v.to_string()))
+    }
+
+    fn read_query_flags(&mut self, version: Version) -> ProtResult<Int> {
+        match version {
+            Version::V3 | Version::V4 => Ok(self.read_byte()? as Int),
+            Version::V5 => self.read_int(),
+        }
     }
 
     fn read_string_map(&mut self) -> ProtResult<StringMap> {
         let len = self.read_short()?;
+        self.check_count(i64::from(len))?;
         let mut map = StringMap::new();
         for _ in 1..=len {
             map.insert(self.read_string()?, self.read_string()?);
@@ -400,6 +538,7 @@ pub trait Decodable<B: io::Read> {
 
     fn read_string_multimap(&mut self) -> ProtResult<StringMultimap> {
         let len = self.read_short()?;
+        self.check_count(i64::from(len))?;
         let mut map = StringMultimap::new();
         for _ in 1..=len {
             map.insert(self.read_string()?, self.read_string_list()?);
@@ -407,9 +546,26 @@ pub trait Decodable<B: io::Read> {
         Ok(map)
     }
 
+    fn read_bytes_map(&mut self) -> ProtResult<BytesMap> {
+        let len = self.read_short()?;
+        self.check_count(i64::from(len))?;
+        let mut map = BytesMap::new();
+        for _ in 1..=len {
+            map.insert(self.read_string()?, self.read_bytes()?);
+        }
+        Ok(map)
+    }
+
     fn read_raw_bytes(&mut self, len: u32) -> ProtResult<Vec<u8>> {
-        let mut v = vec![0; len as usize];
-        self.io().read_exact(&mut v)?;
+        if len > self.limits().max_frame_length() {
+            return Err(ProtError::LimitExceeded("bytes length", u64::from(len)));
+        }
+
+        let mut v = Vec::new();
+        self.io().take(u64::from(len)).read_to_end(&mut v)?;
+        if v.len() != len as usize {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
         Ok(v)
     }
 }
@@ -430,4 +586,8 @@ impl<B: io::Write + io::Read> Decodable<B> for Codec<B> {
     fn io(&mut self) -> &mut B {
         &mut self.io
     }
+
+    fn limits(&self) -> Limits {
+        self.limits
+    }
 }
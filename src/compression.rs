@@ -1,5 +1,21 @@
+use crate::def::{OptionKeys, Version};
+use crate::request::Startup;
+use crate::response::Supported;
+
+#[cfg(feature = "lz4")]
+use lz4::block;
+#[cfg(feature = "snappy")]
+use snap::{decompress_len, max_compress_len, Decoder, Encoder};
+
 use std::io;
 
+pub const PREFERRED_COMPRESSIONS: [Compression; 2] = [Compression::Lz4, Compression::Snappy];
+
+#[cfg(feature = "lz4")]
+pub const LZ4_MAX_LENGTH: usize = 256 * 1024 * 1024;
+#[cfg(feature = "snappy")]
+pub const SNAPPY_MAX_LENGTH: usize = 256 * 1024 * 1024;
+
 #[derive(Clone, Copy, Debug, Display, EnumString, PartialEq)]
 pub enum Compression {
     #[strum(serialize = "lz4")]
@@ -8,7 +24,218 @@ pub enum Compression {
     Snappy,
 }
 
+impl Compression {
+    pub fn compressor(self) -> Option<Box<dyn Compressor>> {
+        match self {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => Some(Box::new(Lz4::new())),
+            #[cfg(not(feature = "lz4"))]
+            Compression::Lz4 => None,
+            #[cfg(feature = "snappy")]
+            Compression::Snappy => Some(Box::new(Snappy::new())),
+            #[cfg(not(feature = "snappy"))]
+            Compression::Snappy => None,
+        }
+    }
+}
+
+pub fn negotiate(supported: &Supported, preference: &[Compression], version: Version, startup: &mut Startup)
+-> Option<Box<dyn Compressor>> {
+    let options = supported.options().get(&OptionKeys::Compression.to_string())?;
+    for compression in preference {
+        if version >= Version::V5 && *compression != Compression::Lz4 {
+            continue;
+        }
+
+        let name = compression.to_string();
+        if !options.iter().any(|o| o.eq_ignore_ascii_case(&name)) {
+            continue;
+        }
+        if let Some(compressor) = compression.compressor() {
+            startup.set_compression(*compression);
+            return Some(compressor);
+        }
+    }
+    None
+}
+
 pub trait Compressor {
-    fn compress(&mut self, v: &[u8]) -> io::Result<Vec<u8>>;
-    fn decompress(&mut self, v: &[u8]) -> io::Result<Vec<u8>>;
+    fn compress(&mut self, src: &[u8], dst: &mut Vec<u8>) -> io::Result<()>;
+    fn decompress(&mut self, src: &[u8], dst: &mut Vec<u8>) -> io::Result<()>;
+    fn decompressed_length(&self, src: &[u8]) -> io::Result<usize>;
+
+    fn compress_segment(&mut self, src: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        self.compress(src, dst)
+    }
+
+    fn decompress_segment(&mut self, src: &[u8], _len: usize, dst: &mut Vec<u8>) -> io::Result<()> {
+        self.decompress(src, dst)
+    }
+}
+
+#[cfg(feature = "lz4")]
+pub struct Lz4 {
+    max_length: usize,
+}
+
+#[cfg(feature = "lz4")]
+impl Default for Lz4 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "lz4")]
+impl Lz4 {
+    pub fn new() -> Self {
+        Lz4 {
+            max_length: LZ4_MAX_LENGTH,
+        }
+    }
+
+    pub fn set_max_length(&mut self, len: usize) {
+        self.max_length = len;
+    }
+
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    fn compress_block(src: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        let bound = block::compress_bound(src.len())?;
+        let offset = dst.len();
+        dst.resize(offset + bound, 0);
+        match block::compress_to_buffer(src, None, false, &mut dst[offset..]) {
+            Ok(n) => {
+                dst.truncate(offset + n);
+                Ok(())
+            },
+            Err(e) => {
+                dst.truncate(offset);
+                Err(e)
+            },
+        }
+    }
+
+    fn decompress_block(&self, src: &[u8], len: usize, dst: &mut Vec<u8>) -> io::Result<()> {
+        if len > self.max_length || len > i32::MAX as usize || src.len() > i32::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "lz4 uncompressed length too large"));
+        }
+        if len == 0 {
+            return Ok(());
+        }
+
+        let offset = dst.len();
+        dst.resize(offset + len, 0);
+        match block::decompress_to_buffer(src, Some(len as i32), &mut dst[offset..]) {
+            Ok(n) if n == len => Ok(()),
+            _ => {
+                dst.truncate(offset);
+                Err(io::Error::new(io::ErrorKind::InvalidData, "lz4 uncompressed length mismatch"))
+            },
+        }
+    }
+}
+
+#[cfg(feature = "lz4")]
+impl Compressor for Lz4 {
+    fn compress(&mut self, src: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        dst.extend_from_slice(&(src.len() as u32).to_be_bytes());
+        Self::compress_block(src, dst)
+    }
+
+    fn decompress(&mut self, src: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        let len = self.decompressed_length(src)?;
+        self.decompress_block(&src[4..], len, dst)
+    }
+
+    fn decompressed_length(&self, src: &[u8]) -> io::Result<usize> {
+        if src.len() < 4 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "lz4 length prefix missing"));
+        }
+        Ok(u32::from_be_bytes([src[0], src[1], src[2], src[3]]) as usize)
+    }
+
+    fn compress_segment(&mut self, src: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        Self::compress_block(src, dst)
+    }
+
+    fn decompress_segment(&mut self, src: &[u8], len: usize, dst: &mut Vec<u8>) -> io::Result<()> {
+        self.decompress_block(src, len, dst)
+    }
+}
+
+#[cfg(feature = "snappy")]
+pub struct Snappy {
+    encoder: Encoder,
+    decoder: Decoder,
+    max_length: usize,
+}
+
+#[cfg(feature = "snappy")]
+impl Default for Snappy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "snappy")]
+impl Snappy {
+    pub fn new() -> Self {
+        Snappy {
+            encoder: Encoder::new(),
+            decoder: Decoder::new(),
+            max_length: SNAPPY_MAX_LENGTH,
+        }
+    }
+
+    pub fn set_max_length(&mut self, len: usize) {
+        self.max_length = len;
+    }
+
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+}
+
+#[cfg(feature = "snappy")]
+impl Compressor for Snappy {
+    fn compress(&mut self, src: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        let offset = dst.len();
+        dst.resize(offset + max_compress_len(src.len()), 0);
+        match self.encoder.compress(src, &mut dst[offset..]) {
+            Ok(n) => {
+                dst.truncate(offset + n);
+                Ok(())
+            },
+            Err(e) => {
+                dst.truncate(offset);
+                Err(e.into())
+            },
+        }
+    }
+
+    fn decompress(&mut self, src: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        let len = self.decompressed_length(src)?;
+        if len > self.max_length {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "snappy uncompressed length too large"));
+        }
+
+        let offset = dst.len();
+        dst.resize(offset + len, 0);
+        match self.decoder.decompress(src, &mut dst[offset..]) {
+            Ok(n) => {
+                dst.truncate(offset + n);
+                Ok(())
+            },
+            Err(e) => {
+                dst.truncate(offset);
+                Err(e.into())
+            },
+        }
+    }
+
+    fn decompressed_length(&self, src: &[u8]) -> io::Result<usize> {
+        Ok(decompress_len(src)?)
+    }
 }
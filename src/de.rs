@@ -0,0 +1,222 @@
+use crate::response::result::ColSpec;
+use crate::result::*;
+use crate::types::*;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+use std::fmt::Display;
+
+impl de::Error for ProtError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ProtError::SerdeErr(msg.to_string())
+    }
+}
+
+pub fn from_data_types<T: DeserializeOwned>(ty: &Opt, v: &DataTypes) -> ProtResult<T> {
+    T::deserialize(Deserializer::new(ty, v))
+}
+
+pub fn from_bytes<T: DeserializeOwned>(ty: &Opt, bytes: &Bytes) -> ProtResult<T> {
+    let v = match bytes {
+        Some(ref bytes) => unmarshal(ty, bytes)?,
+        None => DataTypes::Null,
+    };
+    from_data_types(ty, &v)
+}
+
+pub fn from_row<T: DeserializeOwned>(col_specs: &[ColSpec], row: &[Bytes]) -> ProtResult<T> {
+    let values = col_specs.iter().zip(row.iter()).map(|(col_spec, bytes)| match bytes {
+        Some(ref bytes) => unmarshal(col_spec.ty(), bytes),
+        None => Ok(DataTypes::Null),
+    }).collect::<ProtResult<Vec<DataTypes>>>()?;
+
+    let entries = col_specs.iter().zip(values.iter()).map(|(col_spec, v)| {
+        (Key::Name(col_spec.name()), Deserializer::new(col_spec.ty(), v))
+    });
+    T::deserialize(MapDeserializer::new(entries))
+}
+
+fn element_type(ty: &Opt, i: usize) -> ProtResult<&Opt> {
+    match ty.value {
+        OptValue::List(ref ty) | OptValue::Set(ref ty) => Ok(ty),
+        OptValue::Tuple(ref types) if i < types.len() => Ok(&types[i]),
+        _ => Err(type_mismatch::<DataTypes>(ty)),
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Deserializer<'a> {
+    ty: &'a Opt,
+    v: &'a DataTypes,
+}
+
+impl<'a> Deserializer<'a> {
+    pub fn new(ty: &'a Opt, v: &'a DataTypes) -> Deserializer<'a> {
+        Deserializer {
+            ty,
+            v,
+        }
+    }
+
+    fn elements<I>(self, v: I) -> ProtResult<Vec<Deserializer<'a>>> where I: Iterator<Item = &'a DataTypes> {
+        v.enumerate().map(|(i, v)| Ok(Deserializer::new(element_type(self.ty, i)?, v))).collect()
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Deserializer<'a> {
+    type Error = ProtError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> ProtResult<V::Value> {
+        match self.v {
+            DataTypes::Null => visitor.visit_none(),
+            DataTypes::Ascii(ref v) => visitor.visit_str(v.as_str()),
+            DataTypes::Bigint(v) | DataTypes::Counter(v) => visitor.visit_i64(*v),
+            DataTypes::Blob(ref v) => visitor.visit_bytes(v),
+            DataTypes::Boolean(v) => visitor.visit_bool(*v),
+            DataTypes::Decimal(ref v) => visitor.visit_string(v.to_string()),
+            DataTypes::Double(v) => visitor.visit_f64(*v),
+            DataTypes::Float(v) => visitor.visit_f32(*v),
+            DataTypes::Int(v) => visitor.visit_i32(*v),
+            DataTypes::Timestamp(ref v) => visitor.visit_string(v.to_rfc3339()),
+            DataTypes::Uuid(ref v) | DataTypes::Timeuuid(ref v) => visitor.visit_string(v.to_string()),
+            DataTypes::Varchar(ref v) => visitor.visit_str(v),
+            DataTypes::Varint(ref v) => visitor.visit_string(v.to_string()),
+            DataTypes::Inet(ref v) => visitor.visit_string(v.to_string()),
+            DataTypes::Date(ref v) => visitor.visit_string(v.naive_utc().to_string()),
+            DataTypes::Time(ref v) => visitor.visit_string(v.to_string()),
+            DataTypes::Smallint(v) => visitor.visit_i16(*v),
+            DataTypes::Tinyint(v) => visitor.visit_i8(*v),
+            DataTypes::Duration(ref v) => {
+                let v = vec![i64::from(v.months), i64::from(v.days), v.nanoseconds];
+                visitor.visit_seq(de::value::SeqDeserializer::new(v.into_iter()))
+            },
+            DataTypes::List(ref v) | DataTypes::Tuple(ref v) => {
+                visitor.visit_seq(de::value::SeqDeserializer::new(self.elements(v.iter())?.into_iter()))
+            },
+            DataTypes::Vector(ref v) => {
+                let (ty, _) = self.ty.vector_type().ok_or_else(|| type_mismatch::<DataTypes>(self.ty))?;
+                let elements = v.iter().map(|v| Deserializer::new(&ty, v));
+                visitor.visit_seq(de::value::SeqDeserializer::new(elements))
+            },
+            DataTypes::Set(ref v) => {
+                visitor.visit_seq(de::value::SeqDeserializer::new(self.elements(v.iter())?.into_iter()))
+            },
+            DataTypes::Map(ref v) => {
+                let (key_type, value_type) = match self.ty.value {
+                    OptValue::Map(ref k, ref v) => (k, v),
+                    _ => return Err(type_mismatch::<DataTypes>(self.ty)),
+                };
+                let entries = v.iter().map(|(k, v)| {
+                    (Key::Value(Deserializer::new(key_type, k)), Deserializer::new(value_type, v))
+                });
+                visitor.visit_map(MapDeserializer::new(entries))
+            },
+            DataTypes::Custom(ref v) => Deserializer::new(self.ty, &v.value).deserialize_any(visitor),
+            DataTypes::Udt(ref v) => {
+                let fields = match self.ty.value {
+                    OptValue::Udt(ref udt) => &udt.fields,
+                    _ => return Err(type_mismatch::<DataTypes>(self.ty)),
+                };
+                let entries = fields.iter().zip(v.fields.iter()).map(|((_, ty), (name, v))| {
+                    (Key::Name(name.as_str()), Deserializer::new(ty, v))
+                });
+                visitor.visit_map(MapDeserializer::new(entries))
+            },
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> ProtResult<V::Value> {
+        match self.v {
+            DataTypes::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> ProtResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> ProtResult<V::Value> {
+        match self.v {
+            DataTypes::Ascii(ref v) => visitor.visit_enum(v.as_str().into_deserializer()),
+            DataTypes::Varchar(ref v) => visitor.visit_enum(v.as_str().into_deserializer()),
+            _ => Err(type_mismatch::<DataTypes>(self.ty)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, ProtError> for Deserializer<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+enum Key<'a> {
+    Name(&'a str),
+    Value(Deserializer<'a>),
+}
+
+struct MapDeserializer<'a, I: Iterator<Item = (Key<'a>, Deserializer<'a>)>> {
+    entries: I,
+    value: Option<Deserializer<'a>>,
+}
+
+impl<'a, I: Iterator<Item = (Key<'a>, Deserializer<'a>)>> MapDeserializer<'a, I> {
+    fn new(entries: I) -> Self {
+        MapDeserializer {
+            entries,
+            value: None,
+        }
+    }
+}
+
+impl<'de, 'a, I: Iterator<Item = (Key<'a>, Deserializer<'a>)>> de::Deserializer<'de> for MapDeserializer<'a, I> {
+    type Error = ProtError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> ProtResult<V::Value> {
+        visitor.visit_map(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, 'a, I: Iterator<Item = (Key<'a>, Deserializer<'a>)>> de::MapAccess<'de> for MapDeserializer<'a, I> {
+    type Error = ProtError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> ProtResult<Option<K::Value>> {
+        match self.entries.next() {
+            Some((k, v)) => {
+                self.value = Some(v);
+                let k = match k {
+                    Key::Name(name) => seed.deserialize(de::value::StrDeserializer::<ProtError>::new(name))?,
+                    Key::Value(k) => seed.deserialize(k)?,
+                };
+                Ok(Some(k))
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> ProtResult<V::Value> {
+        match self.value.take() {
+            Some(v) => seed.deserialize(v),
+            None => Err(ProtError::SerdeErr("value without key".to_string())),
+        }
+    }
+}
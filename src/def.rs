@@ -34,7 +34,7 @@ impl BitAndAssign<$F> for $T {
     );
 }
 
-#[derive(Clone, Copy, FromPrimitive)]
+#[derive(Clone, Copy, Debug, EnumIter, FromPrimitive, PartialEq, PartialOrd)]
 pub enum Version {
     V3 = 3,
     V4 = 4,
@@ -48,6 +48,13 @@ impl Version {
             _ => false,
         }
     }
+
+    pub fn query_flags_length(&self) -> u32 {
+        match self {
+            Self::V3 | Self::V4 => len::BYTE,
+            Self::V5 => len::INT,
+        }
+    }
 }
 
 impl Default for Version {
@@ -65,7 +72,7 @@ pub enum Flags {
 
 impl_flags!(Flags, Byte);
 
-#[derive(Debug, FromPrimitive, PartialEq)]
+#[derive(Clone, Copy, Debug, FromPrimitive, PartialEq)]
 pub enum Opcode {
     Error = 0x00,
     Startup = 0x01,
@@ -99,6 +106,8 @@ pub enum ErrorCode {
     ReadFailure = 0x1300,
     FunctionFailure = 0x1400,
     WriteFailure = 0x1500,
+    CdcWriteFailure = 0x1600,
+    CasWriteUnknown = 0x1700,
     SyntaxError = 0x2000,
     Unauthorized = 0x2100,
     Invalid = 0x2200,
@@ -169,6 +178,7 @@ pub enum QueryFlags {
     DefaultTimestamp = 0x20,
     NamesForValues = 0x40,
     Keyspace = 0x80,
+    NowInSeconds = 0x100,
 }
 
 impl_flags!(QueryFlags, Int);
@@ -186,6 +196,7 @@ pub enum BatchFlags {
     DefaultTimestamp = 0x20,
     NamesForValues = 0x40,
     Keyspace = 0x80,
+    NowInSeconds = 0x100,
 }
 
 impl_flags!(BatchFlags, Int);
@@ -237,7 +248,7 @@ pub enum OptionKeys {
 
 pub const CQL_VERSION: &str = "3.0.0";
 
-#[derive(Display, EnumIter, EnumString)]
+#[derive(Debug, Display, EnumIter, EnumString, PartialEq)]
 pub enum WriteType {
     #[strum(serialize = "SIMPLE")]
     Simple,
@@ -249,5 +260,22 @@ pub enum WriteType {
     Counter,
     #[strum(serialize = "BATCH_LOG")]
     BatchLog,
+    #[strum(serialize = "CAS")]
+    Cas,
+    #[strum(serialize = "VIEW")]
+    View,
+    #[strum(serialize = "CDC")]
+    Cdc,
+}
+
+#[derive(Clone, Copy, Debug, FromPrimitive, PartialEq)]
+pub enum RequestFailureReason {
+    Unknown = 0x0000,
+    ReadTooManyTombstones = 0x0001,
+    Timeout = 0x0002,
+    IncompatibleSchema = 0x0003,
+    ReadSize = 0x0004,
+    NodeDown = 0x0005,
+    IndexNotAvailable = 0x0006,
 }
 
@@ -0,0 +1,617 @@
+use crate::registry::{self, CustomCodec};
+use crate::result::*;
+use crate::types::*;
+
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use chrono::{prelude::*, Duration};
+
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display, Formatter},
+    marker::PhantomData,
+    str::FromStr,
+    sync::Arc,
+};
+
+pub const POINT_TYPE: &str = "org.apache.cassandra.db.marshal.PointType";
+pub const LINE_STRING_TYPE: &str = "org.apache.cassandra.db.marshal.LineStringType";
+pub const POLYGON_TYPE: &str = "org.apache.cassandra.db.marshal.PolygonType";
+pub const DATE_RANGE_TYPE: &str = "org.apache.cassandra.db.marshal.DateRangeType";
+
+const WKB_POINT: u32 = 1;
+const WKB_LINE_STRING: u32 = 2;
+const WKB_POLYGON: u32 = 3;
+
+pub fn register() {
+    registry::register(POINT_TYPE, Arc::new(Codec::<Point>::new()));
+    registry::register(LINE_STRING_TYPE, Arc::new(Codec::<LineString>::new()));
+    registry::register(POLYGON_TYPE, Arc::new(Codec::<Polygon>::new()));
+    registry::register(DATE_RANGE_TYPE, Arc::new(Codec::<DateRange>::new()));
+}
+
+fn invalid_format<T>(v: &str) -> ProtError {
+    ProtError::InvalidFormat(std::any::type_name::<T>(), v.to_string())
+}
+
+trait DseType: Sized + Display + FromStr<Err = ProtError> {
+    const CLASS_NAME: &'static str;
+
+    fn encode(&self) -> ProtResult<Vec<u8>>;
+    fn decode(bytes: &[u8]) -> ProtResult<Self>;
+}
+
+struct Codec<T: DseType> {
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DseType> Codec<T> {
+    fn new() -> Codec<T> {
+        Codec {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: DseType> CustomCodec for Codec<T> {
+    fn marshal(&self, class_name: &str, v: &DataTypes) -> ProtResult<Bytes> {
+        match v {
+            DataTypes::Varchar(ref v) => Ok(Some(v.parse::<T>()?.encode()?)),
+            _ => Err(ProtError::TypeMismatch(T::CLASS_NAME, class_name.to_string())),
+        }
+    }
+
+    fn unmarshal(&self, _class_name: &str, bytes: &[u8]) -> ProtResult<DataTypes> {
+        Ok(DataTypes::Varchar(T::decode(bytes)?.to_string()))
+    }
+}
+
+fn check_class<T: DseType>(ty: &Opt) -> ProtResult<()> {
+    match ty.value {
+        OptValue::Custom(ref class_name) if class_name.trim() == T::CLASS_NAME => Ok(()),
+        _ => Err(ProtError::TypeMismatch(T::CLASS_NAME, format!("{:?}", ty.value))),
+    }
+}
+
+macro_rules! impl_dse_cql {
+    ($T:ty) => (
+        impl ToCql for $T {
+            fn to_cql(&self, ty: &Opt) -> ProtResult<Value> {
+                check_class::<$T>(ty)?;
+                Ok(Value::Some(self.encode()?))
+            }
+        }
+
+        impl FromCql for $T {
+            fn from_cql(ty: &Opt, bytes: &Bytes) -> ProtResult<Self> {
+                check_class::<$T>(ty)?;
+                match bytes {
+                    Some(ref bytes) => Self::decode(bytes),
+                    None => Err(ProtError::UnexpectedNull(stringify!($T))),
+                }
+            }
+        }
+    );
+}
+
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> WkbReader<'a> {
+    fn new(mut bytes: &'a [u8], ty: u32) -> ProtResult<WkbReader<'a>> {
+        let little_endian = bytes.read_u8()? == 1;
+        let mut reader = WkbReader {
+            bytes,
+            little_endian,
+        };
+        let v = reader.read_u32()?;
+        if v != ty {
+            return Err(ProtError::InvalidEnumValue("WKB geometry type", v.to_string()));
+        }
+        Ok(reader)
+    }
+
+    fn read_u32(&mut self) -> ProtResult<u32> {
+        let v = if self.little_endian {
+            self.bytes.read_u32::<LittleEndian>()?
+        } else {
+            self.bytes.read_u32::<BigEndian>()?
+        };
+        Ok(v)
+    }
+
+    fn read_f64(&mut self) -> ProtResult<f64> {
+        let v = if self.little_endian {
+            self.bytes.read_f64::<LittleEndian>()?
+        } else {
+            self.bytes.read_f64::<BigEndian>()?
+        };
+        Ok(v)
+    }
+
+    fn read_point(&mut self) -> ProtResult<Point> {
+        Ok(Point::new(self.read_f64()?, self.read_f64()?))
+    }
+
+    fn read_points(&mut self) -> ProtResult<Vec<Point>> {
+        let len = self.read_u32()? as usize;
+        if len * 16 > self.bytes.len() {
+            return Err(ProtError::InvalidLength(len as i64));
+        }
+        (0..len).map(|_| self.read_point()).collect()
+    }
+
+    fn finish<T>(self, v: T) -> ProtResult<T> {
+        if !self.bytes.is_empty() {
+            return Err(ProtError::InvalidLength(self.bytes.len() as i64));
+        }
+        Ok(v)
+    }
+}
+
+fn wkb_header(ty: u32) -> ProtResult<Vec<u8>> {
+    let mut v = vec![1];
+    v.write_u32::<LittleEndian>(ty)?;
+    Ok(v)
+}
+
+fn write_points(v: &mut Vec<u8>, points: &[Point]) -> ProtResult<()> {
+    v.write_u32::<LittleEndian>(points.len() as u32)?;
+    for p in points {
+        v.write_f64::<LittleEndian>(p.x)?;
+        v.write_f64::<LittleEndian>(p.y)?;
+    }
+    Ok(())
+}
+
+fn fmt_points(f: &mut Formatter, points: &[Point]) -> fmt::Result {
+    write!(f, "(")?;
+    for (i, p) in points.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{} {}", p.x, p.y)?;
+    }
+    write!(f, ")")
+}
+
+fn wkt_body<'a, T>(s: &'a str, tag: &str) -> ProtResult<Option<&'a str>> {
+    let s = s.trim();
+    if s.len() < tag.len() || !s[..tag.len()].eq_ignore_ascii_case(tag) {
+        return Err(invalid_format::<T>(s));
+    }
+
+    let body = s[tag.len()..].trim();
+    if body.eq_ignore_ascii_case("EMPTY") {
+        return Ok(None);
+    }
+    Ok(Some(unwrap_parens::<T>(body)?))
+}
+
+fn unwrap_parens<T>(s: &str) -> ProtResult<&str> {
+    let s = s.trim();
+    if s.starts_with('(') && s.ends_with(')') {
+        Ok(&s[1..s.len() - 1])
+    } else {
+        Err(invalid_format::<T>(s))
+    }
+}
+
+fn parse_point<T>(s: &str) -> ProtResult<Point> {
+    let v: Vec<&str> = s.split_whitespace().collect();
+    if v.len() != 2 {
+        return Err(invalid_format::<T>(s));
+    }
+    let x = v[0].parse().map_err(|_| invalid_format::<T>(s))?;
+    let y = v[1].parse().map_err(|_| invalid_format::<T>(s))?;
+    Ok(Point::new(x, y))
+}
+
+fn parse_points<T>(s: &str) -> ProtResult<Vec<Point>> {
+    s.split(',').map(parse_point::<T>).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Point {
+        Point {
+            x,
+            y,
+        }
+    }
+}
+
+impl DseType for Point {
+    const CLASS_NAME: &'static str = POINT_TYPE;
+
+    fn encode(&self) -> ProtResult<Vec<u8>> {
+        let mut v = wkb_header(WKB_POINT)?;
+        v.write_f64::<LittleEndian>(self.x)?;
+        v.write_f64::<LittleEndian>(self.y)?;
+        Ok(v)
+    }
+
+    fn decode(bytes: &[u8]) -> ProtResult<Self> {
+        let mut reader = WkbReader::new(bytes, WKB_POINT)?;
+        let v = reader.read_point()?;
+        reader.finish(v)
+    }
+}
+
+impl Display for Point {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "POINT ({} {})", self.x, self.y)
+    }
+}
+
+impl FromStr for Point {
+    type Err = ProtError;
+
+    fn from_str(s: &str) -> ProtResult<Point> {
+        match wkt_body::<Point>(s, "POINT")? {
+            Some(body) => parse_point::<Point>(body),
+            None => Err(invalid_format::<Point>(s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LineString {
+    pub points: Vec<Point>,
+}
+
+impl LineString {
+    pub fn new(points: Vec<Point>) -> LineString {
+        LineString {
+            points,
+        }
+    }
+}
+
+impl DseType for LineString {
+    const CLASS_NAME: &'static str = LINE_STRING_TYPE;
+
+    fn encode(&self) -> ProtResult<Vec<u8>> {
+        let mut v = wkb_header(WKB_LINE_STRING)?;
+        write_points(&mut v, &self.points)?;
+        Ok(v)
+    }
+
+    fn decode(bytes: &[u8]) -> ProtResult<Self> {
+        let mut reader = WkbReader::new(bytes, WKB_LINE_STRING)?;
+        let v = LineString::new(reader.read_points()?);
+        reader.finish(v)
+    }
+}
+
+impl Display for LineString {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.points.is_empty() {
+            return write!(f, "LINESTRING EMPTY");
+        }
+        write!(f, "LINESTRING ")?;
+        fmt_points(f, &self.points)
+    }
+}
+
+impl FromStr for LineString {
+    type Err = ProtError;
+
+    fn from_str(s: &str) -> ProtResult<LineString> {
+        match wkt_body::<LineString>(s, "LINESTRING")? {
+            Some(body) => Ok(LineString::new(parse_points::<LineString>(body)?)),
+            None => Ok(LineString::default()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Polygon {
+    pub rings: Vec<Vec<Point>>,
+}
+
+impl Polygon {
+    pub fn new(rings: Vec<Vec<Point>>) -> Polygon {
+        Polygon {
+            rings,
+        }
+    }
+}
+
+impl DseType for Polygon {
+    const CLASS_NAME: &'static str = POLYGON_TYPE;
+
+    fn encode(&self) -> ProtResult<Vec<u8>> {
+        let mut v = wkb_header(WKB_POLYGON)?;
+        v.write_u32::<LittleEndian>(self.rings.len() as u32)?;
+        for ring in self.rings.iter() {
+            write_points(&mut v, ring)?;
+        }
+        Ok(v)
+    }
+
+    fn decode(bytes: &[u8]) -> ProtResult<Self> {
+        let mut reader = WkbReader::new(bytes, WKB_POLYGON)?;
+        let len = reader.read_u32()? as usize;
+        if len * 4 > reader.bytes.len() {
+            return Err(ProtError::InvalidLength(len as i64));
+        }
+        let rings = (0..len).map(|_| reader.read_points()).collect::<ProtResult<Vec<Vec<Point>>>>()?;
+        reader.finish(Polygon::new(rings))
+    }
+}
+
+impl Display for Polygon {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.rings.is_empty() {
+            return write!(f, "POLYGON EMPTY");
+        }
+        write!(f, "POLYGON (")?;
+        for (i, ring) in self.rings.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            fmt_points(f, ring)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl FromStr for Polygon {
+    type Err = ProtError;
+
+    fn from_str(s: &str) -> ProtResult<Polygon> {
+        let body = match wkt_body::<Polygon>(s, "POLYGON")? {
+            Some(body) => body,
+            None => return Ok(Polygon::default()),
+        };
+
+        let mut rings = Vec::new();
+        let mut rest = body.trim();
+        while !rest.is_empty() {
+            let end = rest.find(')').ok_or_else(|| invalid_format::<Polygon>(s))?;
+            rings.push(parse_points::<Polygon>(unwrap_parens::<Polygon>(&rest[..=end])?)?);
+            rest = rest[end + 1..].trim_start().trim_start_matches(',').trim_start();
+        }
+        Ok(Polygon::new(rings))
+    }
+}
+
+#[derive(Clone, Copy, Debug, FromPrimitive, PartialEq)]
+pub enum DateRangePrecision {
+    Year = 0,
+    Month = 1,
+    Day = 2,
+    Hour = 3,
+    Minute = 4,
+    Second = 5,
+    Millisecond = 6,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DateRangeBound {
+    Unbounded,
+    Bounded(DateTime<Utc>, DateRangePrecision),
+}
+
+impl DateRangeBound {
+    fn encode(&self, v: &mut Vec<u8>) -> ProtResult<()> {
+        if let DateRangeBound::Bounded(ref t, precision) = self {
+            v.write_i64::<BigEndian>(t.timestamp_millis())?;
+            v.write_u8(*precision as u8)?;
+        }
+        Ok(())
+    }
+
+    fn decode(bytes: &mut &[u8]) -> ProtResult<DateRangeBound> {
+        let t = bytes.read_i64::<BigEndian>()?;
+        let precision = bytes.read_u8()?;
+        let precision = num::FromPrimitive::from_u8(precision)
+            .ok_or_else(|| ProtError::InvalidEnumValue("DateRangePrecision", precision.to_string()))?;
+        let t = Utc.timestamp_millis_opt(t).single().ok_or_else(|| invalid_format::<DateRangeBound>(&t.to_string()))?;
+        Ok(DateRangeBound::Bounded(t, precision))
+    }
+
+    fn round_up(self) -> DateRangeBound {
+        let (t, precision) = match self {
+            DateRangeBound::Bounded(t, precision) => (t, precision),
+            DateRangeBound::Unbounded => return self,
+        };
+
+        let start = match precision {
+            DateRangePrecision::Year => Utc.ymd_opt(t.year(), 1, 1).single(),
+            DateRangePrecision::Month => Utc.ymd_opt(t.year(), t.month(), 1).single(),
+            _ => Some(t.date()),
+        };
+        let next = start.and_then(|start| match precision {
+            DateRangePrecision::Year => Utc.ymd_opt(start.year() + 1, 1, 1).single().map(|d| d.and_hms(0, 0, 0)),
+            DateRangePrecision::Month => start.and_hms(0, 0, 0).checked_add_signed(Duration::days(31))
+                .and_then(|d| Utc.ymd_opt(d.year(), d.month(), 1).single())
+                .map(|d| d.and_hms(0, 0, 0)),
+            DateRangePrecision::Day => start.and_hms(0, 0, 0).checked_add_signed(Duration::days(1)),
+            DateRangePrecision::Hour => start.and_hms(t.hour(), 0, 0).checked_add_signed(Duration::hours(1)),
+            DateRangePrecision::Minute => {
+                start.and_hms(t.hour(), t.minute(), 0).checked_add_signed(Duration::minutes(1))
+            },
+            DateRangePrecision::Second => {
+                start.and_hms(t.hour(), t.minute(), t.second()).checked_add_signed(Duration::seconds(1))
+            },
+            DateRangePrecision::Millisecond => None,
+        });
+
+        match next {
+            Some(next) => DateRangeBound::Bounded(next - Duration::milliseconds(1), precision),
+            None => self,
+        }
+    }
+}
+
+impl Display for DateRangeBound {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (t, precision) = match self {
+            DateRangeBound::Unbounded => return write!(f, "*"),
+            DateRangeBound::Bounded(ref t, precision) => (t, precision),
+        };
+
+        let format = match precision {
+            DateRangePrecision::Year => "%Y",
+            DateRangePrecision::Month => "%Y-%m",
+            DateRangePrecision::Day => "%Y-%m-%d",
+            DateRangePrecision::Hour => "%Y-%m-%dT%H",
+            DateRangePrecision::Minute => "%Y-%m-%dT%H:%M",
+            DateRangePrecision::Second => "%Y-%m-%dT%H:%M:%S",
+            DateRangePrecision::Millisecond => "%Y-%m-%dT%H:%M:%S%.3f",
+        };
+        write!(f, "{}", t.format(format))
+    }
+}
+
+impl FromStr for DateRangeBound {
+    type Err = ProtError;
+
+    fn from_str(s: &str) -> ProtResult<DateRangeBound> {
+        let s = s.trim();
+        if s == "*" {
+            return Ok(DateRangeBound::Unbounded);
+        }
+
+        let invalid = || invalid_format::<DateRangeBound>(s);
+        let v = s.trim_end_matches('Z');
+        let (sign, v) = match v.strip_prefix('-') {
+            Some(v) => (-1, v),
+            None => (1, v.strip_prefix('+').unwrap_or(v)),
+        };
+        let (date, time) = match v.find('T') {
+            Some(i) => (&v[..i], Some(&v[i + 1..])),
+            None => (v, None),
+        };
+
+        let mut fields: Vec<&str> = date.split('-').collect();
+        if fields.len() > 3 || (time.is_some() && fields.len() != 3) {
+            return Err(invalid());
+        }
+        if let Some(time) = time {
+            let mut time: Vec<&str> = time.split(':').collect();
+            if let Some(seconds) = time.pop() {
+                match seconds.find('.') {
+                    Some(i) => time.extend_from_slice(&[&seconds[..i], &seconds[i + 1..]]),
+                    None => time.push(seconds),
+                }
+            }
+            fields.extend(time);
+        }
+        if fields.len() > 7 {
+            return Err(invalid());
+        }
+
+        let mut v = [0u32, 1, 1, 0, 0, 0, 0];
+        for (i, field) in fields.iter().enumerate() {
+            v[i] = field.parse().map_err(|_| invalid())?;
+        }
+        let precision = num::FromPrimitive::from_usize(fields.len() - 1).ok_or_else(invalid)?;
+        let year = i32::try_from(v[0]).map_err(|_| invalid())? * sign;
+        let t = Utc.ymd_opt(year, v[1], v[2]).single().ok_or_else(invalid)?;
+        let t = t.and_hms_milli_opt(v[3], v[4], v[5], v[6]).ok_or_else(invalid)?;
+        Ok(DateRangeBound::Bounded(t, precision))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DateRange {
+    pub lower: DateRangeBound,
+    pub upper: Option<DateRangeBound>,
+}
+
+impl DateRange {
+    pub fn single(bound: DateRangeBound) -> DateRange {
+        DateRange {
+            lower: bound,
+            upper: None,
+        }
+    }
+
+    pub fn range(lower: DateRangeBound, upper: DateRangeBound) -> DateRange {
+        DateRange {
+            lower,
+            upper: Some(upper.round_up()),
+        }
+    }
+}
+
+impl DseType for DateRange {
+    const CLASS_NAME: &'static str = DATE_RANGE_TYPE;
+
+    fn encode(&self) -> ProtResult<Vec<u8>> {
+        let ty = match (self.lower, self.upper) {
+            (DateRangeBound::Bounded(..), None) => 0x00,
+            (DateRangeBound::Bounded(..), Some(DateRangeBound::Bounded(..))) => 0x01,
+            (DateRangeBound::Bounded(..), Some(DateRangeBound::Unbounded)) => 0x02,
+            (DateRangeBound::Unbounded, Some(DateRangeBound::Bounded(..))) => 0x03,
+            (DateRangeBound::Unbounded, Some(DateRangeBound::Unbounded)) => 0x04,
+            (DateRangeBound::Unbounded, None) => 0x05,
+        };
+
+        let mut v = vec![ty];
+        self.lower.encode(&mut v)?;
+        if let Some(ref upper) = self.upper {
+            upper.encode(&mut v)?;
+        }
+        Ok(v)
+    }
+
+    fn decode(mut bytes: &[u8]) -> ProtResult<Self> {
+        let bytes = &mut bytes;
+        let v = match bytes.read_u8()? {
+            0x00 => DateRange::single(DateRangeBound::decode(bytes)?),
+            0x01 => DateRange::range(DateRangeBound::decode(bytes)?, DateRangeBound::decode(bytes)?),
+            0x02 => DateRange::range(DateRangeBound::decode(bytes)?, DateRangeBound::Unbounded),
+            0x03 => DateRange::range(DateRangeBound::Unbounded, DateRangeBound::decode(bytes)?),
+            0x04 => DateRange::range(DateRangeBound::Unbounded, DateRangeBound::Unbounded),
+            0x05 => DateRange::single(DateRangeBound::Unbounded),
+            ty => return Err(ProtError::InvalidEnumValue("DateRangeType", ty.to_string())),
+        };
+
+        if !bytes.is_empty() {
+            return Err(ProtError::InvalidLength(bytes.len() as i64));
+        }
+        Ok(v)
+    }
+}
+
+impl Display for DateRange {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.upper {
+            Some(ref upper) => write!(f, "[{} TO {}]", self.lower, upper),
+            None => write!(f, "{}", self.lower),
+        }
+    }
+}
+
+impl FromStr for DateRange {
+    type Err = ProtError;
+
+    fn from_str(s: &str) -> ProtResult<DateRange> {
+        let v = s.trim();
+        if !v.starts_with('[') {
+            return Ok(DateRange::single(v.parse()?));
+        }
+
+        if !v.ends_with(']') {
+            return Err(invalid_format::<DateRange>(s));
+        }
+        let v = &v[1..v.len() - 1];
+        let i = v.find(" TO ").ok_or_else(|| invalid_format::<DateRange>(s))?;
+        Ok(DateRange::range(v[..i].parse()?, v[i + 4..].parse()?))
+    }
+}
+
+impl_dse_cql!(Point);
+impl_dse_cql!(LineString);
+impl_dse_cql!(Polygon);
+impl_dse_cql!(DateRange);
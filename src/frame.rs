@@ -5,18 +5,19 @@ use crate::message::*;
 use crate::request::*;
 use crate::response::*;
 use crate::result::*;
+use crate::segment::*;
 use crate::types::*;
 
 use num_traits::FromPrimitive;
 
-use std::io::{self, Cursor};
+use std::io::{self, Cursor, Read, Write};
 
 const VERSION_MASK: Byte = 0x80;
 
 macro_rules! match_decode {
     ($codec:expr, $header:expr, $($M:ident),+) => (
         match $header.opcode {
-            $(Opcode::$M => MessageKind::$M($M::decode(&mut $codec)?),)*
+            $(Opcode::$M => MessageKind::$M($M::decode(&mut $codec, $header.version)?),)*
         }
     );
 }
@@ -29,35 +30,146 @@ macro_rules! decode_msg {
     );
 }
 
-fn decode_msg<B: io::Read + io::Write>(mut codec: &mut Codec<B>, header: &Header) -> ProtResult<MessageKind> {
+fn has_custom_payload<M: Message>(m: &M, version: Version) -> bool {
+    m.has_custom_payload() && version >= Version::V4
+}
+
+fn custom_payload_length<M: Message>(m: &M, version: Version) -> u32 {
+    if has_custom_payload(m, version) {
+        m.custom_payload().unwrap().length()
+    } else {
+        0
+    }
+}
+
+fn encode_custom_payload<B, M>(codec: &mut Codec<B>, m: &M, version: Version) -> ProtResult<()>
+where B: io::Read + io::Write, M: Message {
+    if has_custom_payload(m, version) {
+        codec.write_bytes_map(m.custom_payload().unwrap())?;
+    }
+    OK
+}
+
+fn set_custom_payload(m: &mut MessageKind, payload: BytesMap) -> Option<BytesMap> {
+    match m {
+        MessageKind::Query(ref mut m) => m.set_custom_payload(payload),
+        MessageKind::Prepare(ref mut m) => m.set_custom_payload(payload),
+        MessageKind::Execute(ref mut m) => m.set_custom_payload(payload),
+        MessageKind::Batch(ref mut m) => m.set_custom_payload(payload),
+        _ => return Some(payload),
+    }
+    None
+}
+
+fn decode_msg<B: io::Read + io::Write>(mut codec: &mut Codec<B>, header: Header, length: u32) -> ProtResult<Envelope> {
     let tracing_id = if Flags::Tracing.is_set(header.flags) {
         Some(codec.read_uuid()?)
     } else {
         None
     };
 
-    if Flags::Warning.is_set(header.flags) {
-        codec.read_string_list()?;
-    }
+    let warnings = if Flags::Warning.is_set(header.flags) {
+        Some(codec.read_string_list()?)
+    } else {
+        None
+    };
+
+    let custom_payload = if Flags::CustomPayload.is_set(header.flags) {
+        Some(codec.read_bytes_map()?)
+    } else {
+        None
+    };
 
     let mut m = decode_msg!(&mut codec, header);
     if let MessageKind::Result(ref mut result) = m {
         result.set_tracing_id(tracing_id);
     }
-    Ok(m)
+    let custom_payload = custom_payload.and_then(|payload| set_custom_payload(&mut m, payload));
+
+    Ok(Envelope {
+        version: header.version,
+        flags: header.flags,
+        stream_id: header.stream_id,
+        opcode: header.opcode,
+        tracing_id,
+        warnings,
+        custom_payload,
+        length,
+        message: m,
+    })
+}
+
+#[derive(Debug)]
+pub struct Envelope {
+    version: Version,
+    flags: Byte,
+    stream_id: i16,
+    opcode: Opcode,
+    tracing_id: Option<Uuid>,
+    warnings: Option<StringList>,
+    custom_payload: Option<BytesMap>,
+    length: u32,
+    message: MessageKind,
+}
+
+impl Envelope {
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn flags(&self) -> Byte {
+        self.flags
+    }
+
+    pub fn stream_id(&self) -> i16 {
+        self.stream_id
+    }
+
+    pub fn opcode(&self) -> Opcode {
+        self.opcode
+    }
+
+    pub fn tracing_id(&self) -> &Option<Uuid> {
+        &self.tracing_id
+    }
+
+    pub fn warnings(&self) -> &Option<StringList> {
+        &self.warnings
+    }
+
+    pub fn custom_payload(&self) -> &Option<BytesMap> {
+        &self.custom_payload
+    }
+
+    pub fn length(&self) -> u32 {
+        self.length
+    }
+
+    pub fn message(&self) -> &MessageKind {
+        &self.message
+    }
+
+    pub fn into_message(self) -> MessageKind {
+        self.message
+    }
 }
 
 pub struct Frame<B: io::Read + io::Write> {
+    version: Version,
     req_version: Byte,
     rsp_version: Byte,
     flags: Byte,
     stream_id: i16,
-    codec: Codec<B>,
+    codec: Codec<SegmentIo<B>>,
     compressor: Option<Box<dyn Compressor>>,
+    min_compress_length: usize,
+    body: Codec<Cursor<Vec<u8>>>,
+    buf: Vec<u8>,
 }
 
+#[derive(Clone, Copy)]
 struct Header {
-    _version: Version,
+    version: Version,
     flags: Byte,
     stream_id: i16,
     opcode: Opcode,
@@ -76,43 +188,93 @@ impl<B> Frame<B> where B: io::Read + io::Write {
         }
 
         Frame {
+            version,
             req_version: v,
             rsp_version: v | VERSION_MASK,
             flags,
             stream_id,
-            codec: Codec::new(io),
+            codec: Codec::new(SegmentIo::new(io)),
             compressor,
+            min_compress_length: 0,
+            body: Codec::new(Cursor::new(Vec::new())),
+            buf: Vec::new(),
         }
     }
 
     pub fn encode<M: Message>(&mut self, m: &M) -> ProtResult<()> {
-        self.encode_header(m)?;
         if self.compressor.is_some() && m.opcode() != Opcode::Startup {
-            self.compress(m)
+            self.compress(m)?;
         } else {
+            self.encode_header(m, false)?;
             self.encode_length(m)?;
-            self.encode_body(m)
+            self.encode_body(m)?;
         }
+        self.codec.io().flush()?;
+
+        self.switch_to_segments(m.opcode());
+        OK
     }
 
-    pub fn decode(&mut self) -> ProtResult<(i16, MessageKind)> {
+    pub fn decode(&mut self) -> ProtResult<Envelope> {
         let header = self.decode_header()?;
         let len = self.decode_length()?;
-        let m = if self.compressor.is_some() && Flags::Compression.is_set(header.flags) {
-            self.decompress(&header, len)?
+        let envelope = if self.compressor.is_some() && Flags::Compression.is_set(header.flags) {
+            self.decompress(header, len)?
         } else {
-            self.decode_body(&header)?
+            self.decode_body(header, len)?
         };
-        Ok((header.stream_id, m))
+
+        self.switch_to_segments(header.opcode);
+        Ok(envelope)
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn is_segmented(&self) -> bool {
+        self.codec.io_ref().is_enabled()
+    }
+
+    pub fn limits_mut(&mut self) -> &mut Limits {
+        self.codec.limits_mut()
+    }
+
+    pub fn min_compress_length(&self) -> usize {
+        self.min_compress_length
+    }
+
+    pub fn set_min_compress_length(&mut self, len: usize) {
+        self.min_compress_length = len;
+    }
+
+    pub fn set_max_payload_length(&mut self, len: usize) {
+        self.codec.io().set_max_payload_length(len);
+    }
+
+    pub fn set_max_message_length(&mut self, len: usize) {
+        self.codec.io().set_max_message_length(len);
     }
 
     pub fn io_mut(&mut self) -> &mut B {
-        self.codec.io()
+        self.codec.io().get_mut()
+    }
+
+    fn switch_to_segments(&mut self, opcode: Opcode) {
+        if self.version < Version::V5 || self.is_segmented() {
+            return;
+        }
+
+        if let Opcode::Ready | Opcode::Authenticate = opcode {
+            self.flags &= !Flags::Compression;
+            let compressor = self.compressor.take();
+            self.codec.io().enable(compressor);
+        }
     }
 
-    fn encode_header<M: Message>(&mut self, m: &M) -> ProtResult<()> {
+    fn encode_header<M: Message>(&mut self, m: &M, compressed: bool) -> ProtResult<()> {
         self.encode_version(m)?;
-        self.encode_flags(m)?;
+        self.encode_flags(m, compressed)?;
         self.encode_stream_id()?;
         self.encode_opcode(m)
     }
@@ -126,16 +288,16 @@ impl<B> Frame<B> where B: io::Read + io::Write {
         self.codec.write_byte(version)
     }
 
-    fn encode_flags<M: Message>(&mut self, m: &M) -> ProtResult<()> {
+    fn encode_flags<M: Message>(&mut self, m: &M, compressed: bool) -> ProtResult<()> {
         let mut flags = self.flags;
-        if let Opcode::Startup = m.opcode() {
+        if !compressed {
             flags &= !Flags::Compression;
         }
 
         if m.tracing() {
             flags |= Flags::Tracing;
         }
-        if m.has_custom_payload() {
+        if has_custom_payload(m, self.version) {
             flags |= Flags::CustomPayload;
         }
         if m.warning() {
@@ -154,25 +316,37 @@ impl<B> Frame<B> where B: io::Read + io::Write {
     }
 
     fn encode_length<M: Message>(&mut self, m: &M) -> ProtResult<()> {
-        self.codec.write_u32(m.length())
+        self.codec.write_u32(custom_payload_length(m, self.version) + m.length(self.version))
     }
 
     fn encode_body<M: Message>(&mut self, m: &M) -> ProtResult<()> {
-        m.encode(&mut self.codec)
+        encode_custom_payload(&mut self.codec, m, self.version)?;
+        m.encode(&mut self.codec, self.version)
     }
 
     fn compress<M: Message>(&mut self, m: &M) -> ProtResult<()> {
-        let mut codec = Codec::new(Cursor::new(Vec::new()));
-        m.encode(&mut codec)?;
+        self.body.io().get_mut().clear();
+        self.body.io().set_position(0);
+        encode_custom_payload(&mut self.body, m, self.version)?;
+        m.encode(&mut self.body, self.version)?;
+
+        let body = self.body.io_ref().get_ref();
+        if body.len() < self.min_compress_length {
+            self.encode_header(m, false)?;
+            self.codec.write_u32(self.body.io_ref().get_ref().len() as u32)?;
+            return self.codec.write_raw_bytes(self.body.io_ref().get_ref());
+        }
 
-        let v = self.compressor.as_mut().unwrap().compress(codec.io().get_ref())?;
-        self.codec.write_u32(v.len() as u32)?;
-        self.codec.write_raw_bytes(v.as_slice())
+        self.buf.clear();
+        self.compressor.as_mut().unwrap().compress(body, &mut self.buf)?;
+        self.encode_header(m, true)?;
+        self.codec.write_u32(self.buf.len() as u32)?;
+        self.codec.write_raw_bytes(&self.buf)
     }
 
     fn decode_header(&mut self) -> ProtResult<Header> {
         Ok(Header {
-            _version: self.decode_version()?,
+            version: self.decode_version()?,
             flags: self.decode_flags()?,
             stream_id: self.decode_stream_id()?,
             opcode: self.decode_opcode()?,
@@ -181,7 +355,7 @@ impl<B> Frame<B> where B: io::Read + io::Write {
 
     fn decode_version(&mut self) -> ProtResult<Version> {
         let v: Byte = self.codec.read_byte()?;
-        let version: Version = FromPrimitive::from_u8(v & !VERSION_MASK).unwrap();
+        let version: Version = FromPrimitive::from_u8(v & !VERSION_MASK).ok_or(ProtError::UnknownVersion(v))?;
         Ok(version)
     }
 
@@ -195,24 +369,50 @@ impl<B> Frame<B> where B: io::Read + io::Write {
 
     fn decode_opcode(&mut self) -> ProtResult<Opcode> {
         let v: Byte = self.codec.read_byte()?;
-        let opcode: Opcode = FromPrimitive::from_u8(v).unwrap();
+        let opcode: Opcode = FromPrimitive::from_u8(v).ok_or(ProtError::UnknownOpcode(v))?;
         Ok(opcode)
     }
 
     fn decode_length(&mut self) -> ProtResult<u32> {
-        self.codec.read_u32()
+        let len = self.codec.read_u32()?;
+        if len > self.codec.limits().max_frame_length() {
+            return Err(ProtError::LimitExceeded("frame length", u64::from(len)));
+        }
+        Ok(len)
     }
 
-    fn decode_body(&mut self, header: &Header) -> ProtResult<MessageKind> {
-        decode_msg(&mut self.codec, header)
+    fn decode_body(&mut self, header: Header, len: u32) -> ProtResult<Envelope> {
+        self.body.io().get_mut().resize(len as usize, 0);
+        self.body.io().set_position(0);
+        self.codec.io().read_exact(self.body.io().get_mut())?;
+        self.decode_buffered(header, len)
     }
 
-    fn decompress(&mut self, header: &Header, len: u32) -> ProtResult<MessageKind> {
-        let v = self.codec.read_raw_bytes(len)?;
-        let v = self.compressor.as_mut().unwrap().decompress(v.as_slice())?;
+    fn decompress(&mut self, header: Header, len: u32) -> ProtResult<Envelope> {
+        self.buf.resize(len as usize, 0);
+        self.codec.io().read_exact(&mut self.buf)?;
+
+        let compressor = self.compressor.as_mut().unwrap();
+        let n = compressor.decompressed_length(&self.buf)?;
+        if n > self.codec.limits().max_frame_length() as usize {
+            return Err(ProtError::LimitExceeded("decompressed length", n as u64));
+        }
 
-        let mut codec = Codec::new(Cursor::new(v));
-        decode_msg(&mut codec, header)
+        self.body.io().get_mut().clear();
+        self.body.io().set_position(0);
+        compressor.decompress(&self.buf, self.body.io().get_mut())?;
+        self.decode_buffered(header, len)
+    }
+
+    fn decode_buffered(&mut self, header: Header, len: u32) -> ProtResult<Envelope> {
+        self.body.set_limits(self.codec.limits());
+        let envelope = decode_msg(&mut self.body, header, len)?;
+
+        let remaining = self.body.io_ref().get_ref().len() as u64 - self.body.io_ref().position();
+        if remaining > 0 {
+            return Err(ProtError::InvalidLength(remaining as i64));
+        }
+        Ok(envelope)
     }
 }
 
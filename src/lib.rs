@@ -2,6 +2,8 @@
 #![feature(const_vec_new)]
 #![feature(specialization)]
 
+#[macro_use]
+extern crate lazy_static;
 #[macro_use]
 extern crate maplit;
 #[macro_use]
@@ -13,7 +15,15 @@ extern crate strum_macros;
 pub mod result;
 
 pub mod def;
+#[cfg(feature = "dse")]
+pub mod dse;
 pub mod types;
+pub mod registry;
+pub mod row;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "serde")]
+pub mod ser;
 pub mod vint;
 
 pub mod codec;
@@ -24,3 +34,4 @@ pub mod request;
 pub mod response;
 
 pub mod frame;
+pub mod segment;
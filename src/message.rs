@@ -3,13 +3,14 @@ use crate::def::*;
 use crate::request::*;
 use crate::response::*;
 use crate::result::*;
+use crate::types::*;
 
 use std::{fmt::{self, Debug, Display, Formatter}, io};
 
 pub trait Serializable: Display {
-    fn length(&self) -> u32;
-    fn encode<B: io::Read + io::Write>(&self, _codec: &mut Codec<B>) -> ProtResult<()>;
-    fn decode<B: io::Read + io::Write>(_codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized;
+    fn length(&self, _version: Version) -> u32;
+    fn encode<B: io::Read + io::Write>(&self, _codec: &mut Codec<B>, _version: Version) -> ProtResult<()>;
+    fn decode<B: io::Read + io::Write>(_codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized;
 }
 
 pub trait Message: Serializable {
@@ -19,7 +20,8 @@ pub trait Message: Serializable {
 
     fn is_response(&self) -> bool { false }
     fn tracing(&self) -> bool { false }
-    fn has_custom_payload(&self) -> bool { false }
+    fn custom_payload(&self) -> Option<&BytesMap> { None }
+    fn has_custom_payload(&self) -> bool { self.custom_payload().is_some() }
     fn warning(&self) -> bool { false }
     fn opcode(&self) -> Opcode;
 }
@@ -108,11 +110,12 @@ impl Message for $M {
 }
 
 impl Serializable for $M {
-    fn length(&self) -> u32 { 0 }
+    fn length(&self, _version: Version) -> u32 { 0 }
 
-    fn encode<B: io::Read + io::Write>(&self, _codec: &mut Codec<B>) -> ProtResult<()> { OK }
+    fn encode<B: io::Read + io::Write>(&self, _codec: &mut Codec<B>, _version: Version) -> ProtResult<()> { OK }
 
-    fn decode<B: io::Read + io::Write>(_codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(_codec: &mut Codec<B>, _version: Version) -> ProtResult<Self>
+    where Self: Sized {
         Ok(Message::new())
     }
 }
@@ -155,15 +158,15 @@ impl Message for $M {
 }
 
 impl Serializable for $M {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.token.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_bytes(&self.token)
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> {
         Ok($M {
             token: codec.read_bytes()?,
         })
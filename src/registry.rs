@@ -0,0 +1,28 @@
+use crate::result::*;
+use crate::types::*;
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+pub trait CustomCodec: Send + Sync {
+    fn marshal(&self, class_name: &str, v: &DataTypes) -> ProtResult<Bytes>;
+    fn unmarshal(&self, class_name: &str, bytes: &[u8]) -> ProtResult<DataTypes>;
+}
+
+lazy_static! {
+    static ref CODECS: RwLock<HashMap<String, Arc<dyn CustomCodec>>> = RwLock::new(HashMap::new());
+}
+
+pub fn register(class_name: &str, codec: Arc<dyn CustomCodec>) -> Option<Arc<dyn CustomCodec>> {
+    CODECS.write().unwrap().insert(class_name.to_string(), codec)
+}
+
+pub fn unregister(class_name: &str) -> Option<Arc<dyn CustomCodec>> {
+    CODECS.write().unwrap().remove(class_name)
+}
+
+pub fn codec(class_name: &str) -> Option<Arc<dyn CustomCodec>> {
+    let codecs = CODECS.read().unwrap();
+    let base = class_name.find('(').map(|i| &class_name[..i]);
+    codecs.get(class_name).or_else(|| base.and_then(|base| codecs.get(base))).cloned()
+}
@@ -52,7 +52,7 @@ impl BatchQuery {
 }
 
 impl Serializable for BatchQuery {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         let mut len = len::BYTE;
         match self.kind {
             BatchQueryKind::Query(ref s) => len += s.length(),
@@ -63,7 +63,7 @@ impl Serializable for BatchQuery {
         len
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         match self.kind {
             BatchQueryKind::Query(ref s) => {
                 codec.write_byte(0)?;
@@ -83,7 +83,7 @@ impl Serializable for BatchQuery {
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> {
         let kind_num = codec.read_byte()?;
         let kind = if kind_num == 0 {
             BatchQueryKind::Query(codec.read_long_string()?)
@@ -93,6 +93,7 @@ impl Serializable for BatchQuery {
 
         let mut values = Vec::new();
         let len = codec.read_short()?;
+        codec.check_count(i64::from(len))?;
         for _ in 1..=len {
             values.push(codec.read_value()?);
         }
@@ -114,6 +115,8 @@ pub struct Batch {
     serial_consistency: Option<Consistency>,
     timestamp: Option<Long>,
     ks: Option<String>,
+    now_in_seconds: Option<Int>,
+    custom_payload: Option<BytesMap>,
 }
 
 impl Batch {
@@ -141,6 +144,14 @@ impl Batch {
         self.ks = Some(ks.to_string());
     }
 
+    pub fn set_now_in_seconds(&mut self, now_in_seconds: Int) {
+        self.now_in_seconds = Some(now_in_seconds);
+    }
+
+    pub fn set_custom_payload(&mut self, payload: BytesMap) {
+        self.custom_payload = Some(payload);
+    }
+
     pub fn ty(&self) -> &BatchType {
         &self.ty
     }
@@ -165,7 +176,11 @@ impl Batch {
         &self.ks
     }
 
-    fn flags(&self) -> Int {
+    pub fn now_in_seconds(&self) -> &Option<Int> {
+        &self.now_in_seconds
+    }
+
+    fn flags(&self, version: Version) -> Int {
         let mut flags = 0;
         if self.serial_consistency.is_some() {
             flags |= QueryFlags::SerialConsistency;
@@ -173,9 +188,12 @@ impl Batch {
         if self.timestamp.is_some() {
             flags |= QueryFlags::DefaultTimestamp;
         }
-        if self.ks.is_some() {
+        if self.ks.is_some() && version >= Version::V5 {
             flags |= QueryFlags::Keyspace;
         }
+        if self.now_in_seconds.is_some() && version >= Version::V5 {
+            flags |= QueryFlags::NowInSeconds;
+        }
         flags
     }
 }
@@ -185,13 +203,17 @@ impl Message for Batch {
         Batch::default()
     }
 
+    fn custom_payload(&self) -> Option<&BytesMap> {
+        self.custom_payload.as_ref()
+    }
+
     fn opcode(&self) -> Opcode { Opcode::Batch }
 }
 
 impl Serializable for Batch {
-    fn length(&self) -> u32 {
-        let mut len = len::BYTE + len::SHORT + len::INT;
-        len += self.querys.iter().fold(len::SHORT, |len, e| len + e.length());
+    fn length(&self, version: Version) -> u32 {
+        let mut len = len::BYTE + len::SHORT + version.query_flags_length();
+        len += self.querys.iter().fold(len::SHORT, |len, e| len + e.length(version));
 
         if self.serial_consistency.is_some() {
             len += len::SHORT;
@@ -199,21 +221,28 @@ impl Serializable for Batch {
         if self.timestamp.is_some() {
             len += len::LONG;
         }
-        if self.ks.is_some() {
+        if self.ks.is_some() && version >= Version::V5 {
             len += self.ks.length();
         }
+        if self.now_in_seconds.is_some() && version >= Version::V5 {
+            len += len::INT;
+        }
 
         len
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
+        super::check_v5("keyspace", &self.ks, version)?;
+        super::check_v5("now_in_seconds", &self.now_in_seconds, version)?;
+
+        let flags = self.flags(version);
         codec.write_byte(self.ty as Byte)?;
         codec.write_short(self.querys.len() as Short)?;
         for query in &self.querys {
-            query.encode(codec)?;
+            query.encode(codec, version)?;
         }
         codec.write_consistency(self.consistency)?;
-        codec.write_int(self.flags())?;
+        codec.write_query_flags(flags, version)?;
 
         if let Some(serial_consistency) = self.serial_consistency {
             codec.write_consistency(serial_consistency)?;
@@ -221,23 +250,28 @@ impl Serializable for Batch {
         if let Some(timestamp) = self.timestamp {
             codec.write_long(timestamp)?;
         }
-        if let Some(ref ks) = self.ks {
-            codec.write_string(ks)?;
+        if QueryFlags::Keyspace.is_set(flags) {
+            codec.write_string(self.ks.as_ref().unwrap())?;
+        }
+        if QueryFlags::NowInSeconds.is_set(flags) {
+            codec.write_int(self.now_in_seconds.unwrap())?;
         }
 
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
-        let ty = FromPrimitive::from_u8(codec.read_byte()?).unwrap();
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> {
+        let v = codec.read_byte()?;
+        let ty = FromPrimitive::from_u8(v).ok_or_else(|| ProtError::InvalidEnumValue("BatchType", v.to_string()))?;
         let mut querys = Vec::new();
         let len = codec.read_short()?;
+        codec.check_count(i64::from(len))?;
         for _ in 1..=len {
-            querys.push(BatchQuery::decode(codec)?);
+            querys.push(BatchQuery::decode(codec, version)?);
         }
         let consistency = codec.read_consistency()?;
 
-        let flags = codec.read_int()?;
+        let flags = codec.read_query_flags(version)?;
         let serial_consistency = if QueryFlags::SerialConsistency.is_set(flags) {
             Some(codec.read_consistency()?)
         } else {
@@ -253,6 +287,11 @@ impl Serializable for Batch {
         } else {
             None
         };
+        let now_in_seconds = if QueryFlags::NowInSeconds.is_set(flags) {
+            Some(codec.read_int()?)
+        } else {
+            None
+        };
 
         Ok(Batch {
             ty,
@@ -261,6 +300,8 @@ impl Serializable for Batch {
             serial_consistency,
             timestamp,
             ks,
+            now_in_seconds,
+            custom_payload: None,
         })
     }
 }
@@ -2,6 +2,7 @@ use crate::codec::*;
 use crate::def::*;
 use crate::message::*;
 use crate::request::query::QueryParams;
+use crate::response::result::Prepared;
 use crate::result::*;
 use crate::types::*;
 
@@ -10,45 +11,86 @@ use std::io;
 #[derive(Debug, PartialEq)]
 pub struct Execute {
     id: ShortBytes,
+    result_metadata_id: ShortBytes,
     params: QueryParams,
+    custom_payload: Option<BytesMap>,
 }
 
 impl Execute {
     pub fn from(id: ShortBytes, params: QueryParams) -> Self {
         Execute {
             id,
+            result_metadata_id: ShortBytes::new(),
             params,
+            custom_payload: None,
         }
     }
 
+    pub fn from_prepared(prepared: &Prepared, params: QueryParams) -> Self {
+        let mut execute = Execute::from(prepared.id().clone(), params);
+        execute.set_result_metadata_id(prepared.result_metadata_id().clone());
+        execute
+    }
+
+    pub fn set_result_metadata_id(&mut self, result_metadata_id: ShortBytes) {
+        self.result_metadata_id = result_metadata_id;
+    }
+
+    pub fn set_custom_payload(&mut self, payload: BytesMap) {
+        self.custom_payload = Some(payload);
+    }
+
     pub fn id(&self) -> &ShortBytes {
         &self.id
     }
 
+    pub fn result_metadata_id(&self) -> &ShortBytes {
+        &self.result_metadata_id
+    }
+
     pub fn params(&self) -> &QueryParams {
         &self.params
     }
 }
 
 impl Message for Execute {
+    fn custom_payload(&self) -> Option<&BytesMap> {
+        self.custom_payload.as_ref()
+    }
+
     fn opcode(&self) -> Opcode { Opcode::Execute }
 }
 
 impl Serializable for Execute {
-    fn length(&self) -> u32 {
-        self.id.length() + self.params.length()
+    fn length(&self, version: Version) -> u32 {
+        let mut len = self.id.length() + self.params.length(version);
+        if version >= Version::V5 {
+            len += self.result_metadata_id.length();
+        }
+        len
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
         codec.write_short_bytes(&self.id)?;
-        self.params.encode(codec)?;
+        if version >= Version::V5 {
+            codec.write_short_bytes(&self.result_metadata_id)?;
+        }
+        self.params.encode(codec, version)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> {
+        let id = codec.read_short_bytes()?;
+        let result_metadata_id = if version >= Version::V5 {
+            codec.read_short_bytes()?
+        } else {
+            ShortBytes::new()
+        };
         Ok(Execute {
-            id: codec.read_short_bytes()?,
-            params: QueryParams::decode(codec)?,
+            id,
+            result_metadata_id,
+            params: QueryParams::decode(codec, version)?,
+            custom_payload: None,
         })
     }
 }
@@ -1,4 +1,6 @@
+use crate::def::Version;
 use crate::message::Message;
+use crate::result::*;
 
 pub use auth_response::AuthResponse;
 pub use batch::Batch;
@@ -19,3 +21,10 @@ pub mod register;
 pub mod startup;
 
 pub trait Request: Message {}
+
+fn check_v5<T>(name: &'static str, v: &Option<T>, version: Version) -> ProtResult<()> {
+    if v.is_some() && version < Version::V5 {
+        return Err(ProtError::UnsupportedVersion(name, version as u8));
+    }
+    OK
+}
@@ -10,6 +10,7 @@ use std::io;
 pub struct Prepare {
     query: LongString,
     ks: Option<String>,
+    custom_payload: Option<BytesMap>,
 }
 
 impl Prepare {
@@ -28,6 +29,10 @@ impl Prepare {
         self.ks = Some(ks.to_string());
     }
 
+    pub fn set_custom_payload(&mut self, payload: BytesMap) {
+        self.custom_payload = Some(payload);
+    }
+
     pub fn query(&self) -> &str {
         &self.query.0
     }
@@ -38,16 +43,28 @@ impl Prepare {
 }
 
 impl Message for Prepare {
+    fn custom_payload(&self) -> Option<&BytesMap> {
+        self.custom_payload.as_ref()
+    }
+
     fn opcode(&self) -> Opcode { Opcode::Prepare }
 }
 
 impl Serializable for Prepare {
-    fn length(&self) -> u32 {
-        self.query.length() + len::INT + self.ks.length()
+    fn length(&self, version: Version) -> u32 {
+        match version {
+            Version::V3 | Version::V4 => self.query.length(),
+            Version::V5 => self.query.length() + len::INT + self.ks.length(),
+        }
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
+        super::check_v5("keyspace", &self.ks, version)?;
         codec.write_long_string(&self.query)?;
+        if version < Version::V5 {
+            return OK;
+        }
+
         if let Some(ref ks) = self.ks {
             codec.write_int(PrepareFlags::Keyspace as Int)?;
             codec.write_string(ks)?;
@@ -57,8 +74,16 @@ impl Serializable for Prepare {
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> {
         let query = codec.read_long_string()?;
+        if version < Version::V5 {
+            return Ok(Prepare {
+                query,
+                ks: None,
+                custom_payload: None,
+            });
+        }
+
         let flags = codec.read_int()?;
         let ks = if PrepareFlags::Keyspace.is_set(flags) {
             Some(codec.read_string()?)
@@ -69,6 +94,7 @@ impl Serializable for Prepare {
         Ok(Prepare {
             query,
             ks,
+            custom_payload: None,
         })
     }
 }
@@ -14,11 +14,13 @@ pub struct QueryParams {
     consistency: Consistency,
     names: Vec<String>,
     values: Vec<Value>,
+    skip_metadata: bool,
     result_page_size: Option<Int>,
     paging_state: Bytes,
     serial_consistency: Option<Consistency>,
     timestamp: Option<Long>,
     ks: Option<String>,
+    now_in_seconds: Option<Int>,
 }
 
 impl QueryParams {
@@ -34,6 +36,10 @@ impl QueryParams {
         self.values = values;
     }
 
+    pub fn set_skip_metadata(&mut self, skip_metadata: bool) {
+        self.skip_metadata = skip_metadata;
+    }
+
     pub fn set_result_page_size(&mut self, size: Int) {
         self.result_page_size = Some(size);
     }
@@ -54,6 +60,10 @@ impl QueryParams {
         self.ks = Some(ks.to_string());
     }
 
+    pub fn set_now_in_seconds(&mut self, now_in_seconds: Int) {
+        self.now_in_seconds = Some(now_in_seconds);
+    }
+
     pub fn consistency(&self) -> &Consistency {
         &self.consistency
     }
@@ -66,6 +76,10 @@ impl QueryParams {
         &self.values
     }
 
+    pub fn skip_metadata(&self) -> bool {
+        self.skip_metadata
+    }
+
     pub fn result_page_size(&self) -> &Option<Int> {
         &self.result_page_size
     }
@@ -86,7 +100,11 @@ impl QueryParams {
         &self.ks
     }
 
-    fn flags(&self) -> Int {
+    pub fn now_in_seconds(&self) -> &Option<Int> {
+        &self.now_in_seconds
+    }
+
+    fn flags(&self, version: Version) -> Int {
         let mut flags = 0;
         if !self.values.is_empty() {
             flags |= QueryFlags::Values;
@@ -96,6 +114,9 @@ impl QueryParams {
             }
         }
 
+        if self.skip_metadata {
+            flags |= QueryFlags::SkipMetadata;
+        }
         if self.result_page_size.is_some() {
             flags |= QueryFlags::PageSize;
         }
@@ -108,16 +129,19 @@ impl QueryParams {
         if self.timestamp.is_some() {
             flags |= QueryFlags::DefaultTimestamp;
         }
-        if self.ks.is_some() {
+        if self.ks.is_some() && version >= Version::V5 {
             flags |= QueryFlags::Keyspace;
         }
+        if self.now_in_seconds.is_some() && version >= Version::V5 {
+            flags |= QueryFlags::NowInSeconds;
+        }
         flags
     }
 }
 
 impl Serializable for QueryParams {
-    fn length(&self) -> u32 {
-        let mut len = len::SHORT + len::INT;
+    fn length(&self, version: Version) -> u32 {
+        let mut len = len::SHORT + version.query_flags_length();
         if !self.values.is_empty() {
             len += self.values.iter().fold(len::SHORT, |len, e| len + e.length());
             len += self.names.iter().map(|e| e.length()).sum::<u32>();
@@ -134,15 +158,22 @@ impl Serializable for QueryParams {
         if self.timestamp.is_some() {
             len += len::LONG;
         }
-        if self.ks.is_some() {
+        if self.ks.is_some() && version >= Version::V5 {
             len += self.ks.length();
         }
+        if self.now_in_seconds.is_some() && version >= Version::V5 {
+            len += len::INT;
+        }
         len
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
+        super::check_v5("keyspace", &self.ks, version)?;
+        super::check_v5("now_in_seconds", &self.now_in_seconds, version)?;
+
+        let flags = self.flags(version);
         codec.write_consistency(self.consistency)?;
-        codec.write_int(self.flags())?;
+        codec.write_query_flags(flags, version)?;
 
         if !self.values.is_empty() {
             codec.write_short(self.values.len() as Short)?;
@@ -167,21 +198,25 @@ impl Serializable for QueryParams {
         if let Some(timestamp) = self.timestamp {
             codec.write_long(timestamp)?;
         }
-        if let Some(ref ks) = self.ks {
-            codec.write_string(ks)?;
+        if QueryFlags::Keyspace.is_set(flags) {
+            codec.write_string(self.ks.as_ref().unwrap())?;
+        }
+        if QueryFlags::NowInSeconds.is_set(flags) {
+            codec.write_int(self.now_in_seconds.unwrap())?;
         }
 
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> {
         let consistency = codec.read_consistency()?;
-        let flags = codec.read_int()?;
+        let flags = codec.read_query_flags(version)?;
 
         let mut names = Vec::new();
         let mut values = Vec::new();
         if QueryFlags::Values.is_set(flags) {
             let len = codec.read_short()?;
+            codec.check_count(i64::from(len))?;
             let has_names = QueryFlags::NamesForValues.is_set(flags);
             for _ in 1..=len {
                 if has_names {
@@ -191,6 +226,7 @@ impl Serializable for QueryParams {
             }
         }
 
+        let skip_metadata = QueryFlags::SkipMetadata.is_set(flags);
         let result_page_size = if QueryFlags::PageSize.is_set(flags) {
             Some(codec.read_int()?)
         } else {
@@ -216,16 +252,23 @@ impl Serializable for QueryParams {
         } else {
             None
         };
+        let now_in_seconds = if QueryFlags::NowInSeconds.is_set(flags) {
+            Some(codec.read_int()?)
+        } else {
+            None
+        };
 
         Ok(QueryParams {
             consistency,
             names,
             values,
+            skip_metadata,
             result_page_size,
             paging_state,
             serial_consistency,
             timestamp,
             ks,
+            now_in_seconds,
         })
     }
 }
@@ -237,6 +280,7 @@ pub struct Query {
     query: LongString,
     params: QueryParams,
     tracing: bool,
+    custom_payload: Option<BytesMap>,
 }
 
 impl Query {
@@ -255,6 +299,10 @@ impl Query {
         self.params = params;
     }
 
+    pub fn set_custom_payload(&mut self, payload: BytesMap) {
+        self.custom_payload = Some(payload);
+    }
+
     pub fn tracing_on(&mut self) {
         self.tracing = true;
     }
@@ -277,6 +325,10 @@ impl Query {
 }
 
 impl Message for Query {
+    fn custom_payload(&self) -> Option<&BytesMap> {
+        self.custom_payload.as_ref()
+    }
+
     fn tracing(&self) -> bool {
         self.tracing
     }
@@ -285,21 +337,22 @@ impl Message for Query {
 }
 
 impl Serializable for Query {
-    fn length(&self) -> u32 {
-        self.query.length() + self.params.length()
+    fn length(&self, version: Version) -> u32 {
+        self.query.length() + self.params.length(version)
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
         codec.write_long_string(&self.query)?;
-        self.params.encode(codec)?;
+        self.params.encode(codec, version)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> {
         Ok(Query {
             query: codec.read_long_string()?,
-            params: QueryParams::decode(codec)?,
+            params: QueryParams::decode(codec, version)?,
             tracing: false,
+            custom_payload: None,
         })
     }
 }
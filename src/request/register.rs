@@ -38,17 +38,21 @@ impl Message for Register {
 }
 
 impl Serializable for Register {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.events.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_string_list(&self.events)
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> {
+        let events = codec.read_string_list()?;
+        for e in &events {
+            EventType::from_str(e).map_err(|_| ProtError::UnknownEventType(e.clone()))?;
+        }
         Ok(Register {
-            events: codec.read_string_list()?,
+            events,
         })
     }
 }
@@ -18,7 +18,7 @@ impl Startup {
     }
 
     pub fn compression(&self) -> Option<Compression> {
-        self.options.get(&OptionKeys::Compression.to_string()).map(|c| FromStr::from_str(c.as_str()).unwrap())
+        self.options.get(&OptionKeys::Compression.to_string()).and_then(|c| FromStr::from_str(c.as_str()).ok())
     }
 }
 
@@ -33,15 +33,15 @@ impl Message for Startup {
 }
 
 impl Serializable for Startup {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.options.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_sting_map(&self.options)
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> {
         Ok(Startup {
             options: codec.read_string_map()?,
         })
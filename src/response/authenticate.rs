@@ -38,15 +38,15 @@ impl Message for Authenticate {
 }
 
 impl Serializable for Authenticate {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.authenticator.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_string(&self.authenticator)
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> {
         Ok(Authenticate {
             authenticator: codec.read_string()?,
         })
@@ -12,6 +12,54 @@ use std::{
     str::FromStr
 };
 
+fn decode_write_type<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<String> {
+    let write_type = codec.read_string()?;
+    WriteType::from_str(&write_type).map_err(|_| ProtError::InvalidEnumValue("WriteType", write_type.clone()))?;
+    Ok(write_type)
+}
+
+fn failures_length(reasonmap: &[(InetAddr, Short)], version: Version) -> u32 {
+    if version < Version::V5 {
+        return len::INT;
+    }
+    len::INT + reasonmap.iter().map(|e| e.0.length() + len::SHORT).sum::<u32>()
+}
+
+fn encode_failures<B: io::Read + io::Write>(codec: &mut Codec<B>, numfailures: Int, reasonmap: &[(InetAddr, Short)],
+                                            version: Version) -> ProtResult<()> {
+    if version < Version::V5 {
+        return codec.write_int(numfailures);
+    }
+
+    codec.write_int(reasonmap.len() as Int)?;
+    for e in reasonmap {
+        codec.write_inetaddr(&e.0)?;
+        codec.write_short(e.1)?;
+    }
+    OK
+}
+
+fn decode_failures<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version)
+-> ProtResult<(Int, Vec<(InetAddr, Short)>)> {
+    let len = codec.read_int()?;
+    let mut reasonmap = Vec::new();
+    if version < Version::V5 {
+        return Ok((len, reasonmap));
+    }
+
+    codec.check_count(i64::from(len))?;
+    for _ in 1..=len {
+        reasonmap.push((codec.read_inetaddr()?, codec.read_short()?));
+    }
+    Ok((len, reasonmap))
+}
+
+fn failure_reasons(reasonmap: &[(InetAddr, Short)]) -> Vec<(InetAddr, RequestFailureReason)> {
+    reasonmap.iter()
+        .map(|e| (e.0, FromPrimitive::from_u16(e.1).unwrap_or(RequestFailureReason::Unknown)))
+        .collect()
+}
+
 #[derive(Debug, PartialEq)]
 pub struct UnavailableException {
     cl: Consistency,
@@ -42,18 +90,18 @@ impl UnavailableException {
 }
 
 impl Serializable for UnavailableException {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         len::SHORT + len::INT * 2
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_consistency(self.consistency())?;
         codec.write_int(self.required)?;
         codec.write_int(self.alive)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
         Ok(UnavailableException {
             cl: codec.read_consistency()?,
             required: codec.read_int()?,
@@ -68,6 +116,7 @@ pub struct WriteTimeout {
     received: Int,
     blockfor: Int,
     write_type: String,
+    contentions: Option<Short>,
 }
 
 impl WriteTimeout {
@@ -77,9 +126,14 @@ impl WriteTimeout {
             received,
             blockfor,
             write_type: write_type.to_string(),
+            contentions: None,
         }
     }
 
+    pub fn set_contentions(&mut self, contentions: Short) {
+        self.contentions = Some(contentions);
+    }
+
     pub fn consistency(&self) -> Consistency {
         self.cl
     }
@@ -95,28 +149,48 @@ impl WriteTimeout {
     pub fn write_type(&self) -> WriteType {
         FromStr::from_str(&self.write_type).unwrap()
     }
+
+    pub fn contentions(&self) -> &Option<Short> {
+        &self.contentions
+    }
+
+    fn has_contentions(&self, version: Version) -> bool {
+        version >= Version::V5 && self.write_type() == WriteType::Cas
+    }
 }
 
 impl Serializable for WriteTimeout {
-    fn length(&self) -> u32 {
-        len::SHORT + len::INT * 2 + self.write_type.length()
+    fn length(&self, version: Version) -> u32 {
+        let mut len = len::SHORT + len::INT * 2 + self.write_type.length();
+        if self.has_contentions(version) {
+            len += len::SHORT;
+        }
+        len
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
         codec.write_consistency(self.consistency())?;
         codec.write_int(self.received)?;
         codec.write_int(self.blockfor)?;
         codec.write_string(&self.write_type)?;
+        if self.has_contentions(version) {
+            codec.write_short(self.contentions.unwrap_or(0))?;
+        }
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
-        Ok(WriteTimeout {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> where Self: Sized {
+        let mut e = WriteTimeout {
             cl: codec.read_consistency()?,
             received: codec.read_int()?,
             blockfor: codec.read_int()?,
-            write_type: codec.read_string()?,
-        })
+            write_type: decode_write_type(codec)?,
+            contentions: None,
+        };
+        if e.has_contentions(version) {
+            e.contentions = Some(codec.read_short()?);
+        }
+        Ok(e)
     }
 }
 
@@ -156,11 +230,11 @@ impl ReadTimeout {
 }
 
 impl Serializable for ReadTimeout {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         len::SHORT + len::INT * 2 + len::BYTE
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_consistency(self.consistency())?;
         codec.write_int(self.received)?;
         codec.write_int(self.blockfor)?;
@@ -168,7 +242,7 @@ impl Serializable for ReadTimeout {
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
         Ok(ReadTimeout {
             cl: codec.read_consistency()?,
             received: codec.read_int()?,
@@ -183,6 +257,7 @@ pub struct ReadFailure {
     cl: Consistency,
     received: Int,
     blockfor: Int,
+    numfailures: Int,
     reasonmap: Vec<(InetAddr, Short)>,
     data_present: Byte,
 }
@@ -194,11 +269,16 @@ impl ReadFailure {
             cl,
             received,
             blockfor,
+            numfailures: reasonmap.len() as Int,
             reasonmap,
             data_present,
         }
     }
 
+    pub fn set_numfailures(&mut self, numfailures: Int) {
+        self.numfailures = numfailures;
+    }
+
     pub fn consistency(&self) -> Consistency {
         self.cl
     }
@@ -211,47 +291,45 @@ impl ReadFailure {
         self.blockfor
     }
 
+    pub fn numfailures(&self) -> Int {
+        self.numfailures
+    }
+
     pub fn reasonmap(&self) -> &Vec<(InetAddr, Short)> {
         &self.reasonmap
     }
 
+    pub fn reasons(&self) -> Vec<(InetAddr, RequestFailureReason)> {
+        failure_reasons(&self.reasonmap)
+    }
+
     pub fn data_present(&self) -> Byte {
         self.data_present
     }
 }
 
 impl Serializable for ReadFailure {
-    fn length(&self) -> u32 {
-        len::SHORT + len::INT * 2
-                + len::INT + self.reasonmap.iter().map(|e| e.0.length() + len::SHORT).sum::<u32>()
-                + len::BYTE
+    fn length(&self, version: Version) -> u32 {
+        len::SHORT + len::INT * 2 + failures_length(&self.reasonmap, version) + len::BYTE
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
         codec.write_consistency(self.consistency())?;
         codec.write_int(self.received)?;
         codec.write_int(self.blockfor)?;
 
-        codec.write_int(self.reasonmap.len() as Int)?;
-        for e in &self.reasonmap {
-            codec.write_inetaddr(&e.0)?;
-            codec.write_short(e.1)?;
-        }
+        encode_failures(codec, self.numfailures, &self.reasonmap, version)?;
 
         codec.write_byte(self.data_present)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> where Self: Sized {
         let cl = codec.read_consistency()?;
         let received = codec.read_int()?;
         let blockfor = codec.read_int()?;
 
-        let mut reasonmap = Vec::new();
-        let len = codec.read_int()?;
-        for _ in 1..=len {
-            reasonmap.push((codec.read_inetaddr()?, codec.read_short()?));
-        }
+        let (numfailures, reasonmap) = decode_failures(codec, version)?;
 
         let data_present = codec.read_byte()?;
 
@@ -259,6 +337,7 @@ impl Serializable for ReadFailure {
             cl,
             received,
             blockfor,
+            numfailures,
             reasonmap,
             data_present,
         })
@@ -295,18 +374,18 @@ impl FunctionFailure {
 }
 
 impl Serializable for FunctionFailure {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.ks.length() + self.function.length() + self.arg_types.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_string(&self.ks)?;
         codec.write_string(&self.function)?;
         codec.write_string_list(&self.arg_types)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
         Ok(FunctionFailure {
             ks: codec.read_string()?,
             function: codec.read_string()?,
@@ -320,6 +399,7 @@ pub struct WriteFailure {
     cl: Consistency,
     received: Int,
     blockfor: Int,
+    numfailures: Int,
     reasonmap: Vec<(InetAddr, Short)>,
     write_type: String,
 }
@@ -331,11 +411,16 @@ impl WriteFailure {
             cl,
             received,
             blockfor,
+            numfailures: reasonmap.len() as Int,
             reasonmap,
             write_type: write_type.to_string(),
         }
     }
 
+    pub fn set_numfailures(&mut self, numfailures: Int) {
+        self.numfailures = numfailures;
+    }
+
     pub fn consistency(&self) -> Consistency {
         self.cl
     }
@@ -348,60 +433,109 @@ impl WriteFailure {
         self.blockfor
     }
 
+    pub fn numfailures(&self) -> Int {
+        self.numfailures
+    }
+
     pub fn reasonmap(&self) -> &Vec<(InetAddr, Short)> {
         &self.reasonmap
     }
 
+    pub fn reasons(&self) -> Vec<(InetAddr, RequestFailureReason)> {
+        failure_reasons(&self.reasonmap)
+    }
+
     pub fn write_type(&self) -> WriteType {
         FromStr::from_str(&self.write_type).unwrap()
     }
 }
 
 impl Serializable for WriteFailure {
-    fn length(&self) -> u32 {
-        len::SHORT + len::INT * 2
-                + len::INT + self.reasonmap.iter().map(|e| e.0.length() + len::SHORT).sum::<u32>()
-                + self.write_type.length()
+    fn length(&self, version: Version) -> u32 {
+        len::SHORT + len::INT * 2 + failures_length(&self.reasonmap, version) + self.write_type.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
         codec.write_consistency(self.consistency())?;
         codec.write_int(self.received)?;
         codec.write_int(self.blockfor)?;
 
-        codec.write_int(self.reasonmap.len() as Int)?;
-        for e in &self.reasonmap {
-            codec.write_inetaddr(&e.0)?;
-            codec.write_short(e.1)?;
-        }
+        encode_failures(codec, self.numfailures, &self.reasonmap, version)?;
 
         codec.write_string(&self.write_type)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> where Self: Sized {
         let cl = codec.read_consistency()?;
         let received = codec.read_int()?;
         let blockfor = codec.read_int()?;
 
-        let mut reasonmap = Vec::new();
-        let len = codec.read_int()?;
-        for _ in 1..=len {
-            reasonmap.push((codec.read_inetaddr()?, codec.read_short()?));
-        }
+        let (numfailures, reasonmap) = decode_failures(codec, version)?;
 
-        let write_type = codec.read_string()?;
+        let write_type = decode_write_type(codec)?;
 
         Ok(WriteFailure {
             cl,
             received,
             blockfor,
+            numfailures,
             reasonmap,
             write_type,
         })
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct CasWriteUnknown {
+    cl: Consistency,
+    received: Int,
+    blockfor: Int,
+}
+
+impl CasWriteUnknown {
+    pub fn new(cl: Consistency, received: Int, blockfor: Int) -> Self {
+        CasWriteUnknown {
+            cl,
+            received,
+            blockfor,
+        }
+    }
+
+    pub fn consistency(&self) -> Consistency {
+        self.cl
+    }
+
+    pub fn received(&self) -> Int {
+        self.received
+    }
+
+    pub fn blockfor(&self) -> Int {
+        self.blockfor
+    }
+}
+
+impl Serializable for CasWriteUnknown {
+    fn length(&self, _version: Version) -> u32 {
+        len::SHORT + len::INT * 2
+    }
+
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
+        codec.write_consistency(self.consistency())?;
+        codec.write_int(self.received)?;
+        codec.write_int(self.blockfor)?;
+        OK
+    }
+
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
+        Ok(CasWriteUnknown {
+            cl: codec.read_consistency()?,
+            received: codec.read_int()?,
+            blockfor: codec.read_int()?,
+        })
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct AlreadyExists {
     ks: String,
@@ -426,17 +560,17 @@ impl AlreadyExists {
 }
 
 impl Serializable for AlreadyExists {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.ks.length() + self.table.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_string(&self.ks)?;
         codec.write_string(&self.table)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
         Ok(AlreadyExists {
             ks: codec.read_string()?,
             table: codec.read_string()?,
@@ -462,15 +596,15 @@ impl Unprepared {
 }
 
 impl Serializable for Unprepared {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.id.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_short_bytes(&self.id)
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
         Ok(Unprepared {
             id: codec.read_short_bytes()?,
         })
@@ -486,6 +620,7 @@ pub enum ExceptionKind {
     ReadFailure(ReadFailure),
     FunctionFailure(FunctionFailure),
     WriteFailure(WriteFailure),
+    CasWriteUnknown(CasWriteUnknown),
     AlreadyExists(AlreadyExists),
     Unprepared(Unprepared),
 }
@@ -500,27 +635,27 @@ macro_rules! match_error_code {
 }
 
 macro_rules! match_length {
-    ($sf:expr, $($M:ident),+) => (
+    ($sf:expr, $version:expr, $($M:ident),+) => (
         match $sf {
-            $(ExceptionKind::$M(ref e) => e.length(),)*
+            $(ExceptionKind::$M(ref e) => e.length($version),)*
             Self::None => 0,
         }
     );
 }
 
 macro_rules! match_encode {
-    ($sf:expr, $codec:expr, $($M:ident),+) => (
+    ($sf:expr, $codec:expr, $version:expr, $($M:ident),+) => (
         match $sf {
-            $(ExceptionKind::$M(ref e) => e.encode($codec),)*
+            $(ExceptionKind::$M(ref e) => e.encode($codec, $version),)*
             Self::None => OK,
         }
     );
 }
 
 macro_rules! match_decode {
-    ($code:expr, $codec:expr, $($M:ident),+) => (
+    ($code:expr, $codec:expr, $version:expr, $($M:ident),+) => (
         match $code {
-            $(ErrorCode::$M => ExceptionKind::$M($M::decode($codec)?),)*
+            $(ErrorCode::$M => ExceptionKind::$M($M::decode($codec, $version)?),)*
             _ => ExceptionKind::None,
         }
     );
@@ -529,22 +664,22 @@ macro_rules! match_decode {
 impl ExceptionKind {
     fn error_code(&self) -> ErrorCode {
         match_error_code!(self, UnavailableException, WriteTimeout, ReadTimeout, ReadFailure,
-                          FunctionFailure, WriteFailure, AlreadyExists, Unprepared)
+                          FunctionFailure, WriteFailure, CasWriteUnknown, AlreadyExists, Unprepared)
     }
 
-    fn length(&self) -> u32 {
-        match_length!(self, UnavailableException, WriteTimeout, ReadTimeout, ReadFailure,
-                      FunctionFailure, WriteFailure, AlreadyExists, Unprepared)
+    fn length(&self, version: Version) -> u32 {
+        match_length!(self, version, UnavailableException, WriteTimeout, ReadTimeout, ReadFailure,
+                      FunctionFailure, WriteFailure, CasWriteUnknown, AlreadyExists, Unprepared)
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
-        match_encode!(self, codec, UnavailableException, WriteTimeout, ReadTimeout, ReadFailure,
-                      FunctionFailure, WriteFailure, AlreadyExists, Unprepared)
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
+        match_encode!(self, codec, version, UnavailableException, WriteTimeout, ReadTimeout, ReadFailure,
+                      FunctionFailure, WriteFailure, CasWriteUnknown, AlreadyExists, Unprepared)
     }
 
-    fn decode<B: io::Read + io::Write>(code: ErrorCode, codec: &mut Codec<B>) -> ProtResult<Self> {
-        let e = match_decode!(code, codec, UnavailableException, WriteTimeout, ReadTimeout, ReadFailure,
-                              FunctionFailure, WriteFailure, AlreadyExists, Unprepared);
+    fn decode<B: io::Read + io::Write>(code: ErrorCode, codec: &mut Codec<B>, version: Version) -> ProtResult<Self> {
+        let e = match_decode!(code, codec, version, UnavailableException, WriteTimeout, ReadTimeout, ReadFailure,
+                              FunctionFailure, WriteFailure, CasWriteUnknown, AlreadyExists, Unprepared);
         Ok(e)
     }
 }
@@ -556,6 +691,7 @@ impl_display!(ReadTimeout);
 impl_display!(ReadFailure);
 impl_display!(FunctionFailure);
 impl_display!(WriteFailure);
+impl_display!(CasWriteUnknown);
 impl_display!(AlreadyExists);
 impl_display!(Unprepared);
 
@@ -622,23 +758,24 @@ impl Message for Error {
 }
 
 impl Serializable for Error {
-    fn length(&self) -> u32 {
-        len::INT + self.msg.length() + self.e.length()
+    fn length(&self, version: Version) -> u32 {
+        len::INT + self.msg.length() + self.e.length(version)
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
         codec.write_int(self.code as Int)?;
         codec.write_string(&self.msg)?;
-        self.e.encode(codec)?;
+        self.e.encode(codec, version)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
-        let code = FromPrimitive::from_i32(codec.read_int()?).unwrap();
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> {
+        let v = codec.read_int()?;
+        let code = FromPrimitive::from_i32(v).ok_or(ProtError::UnknownErrorCode(v))?;
         Ok(Error {
             code,
             msg: codec.read_string()?,
-            e: ExceptionKind::decode(code, codec)?,
+            e: ExceptionKind::decode(code, codec, version)?,
         })
     }
 }
@@ -37,19 +37,21 @@ impl $C {
 }
 
 impl Serializable for $C {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.change.length() + self.node.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_string(&self.change)?;
         codec.write_inet(&self.node)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
+        let change = codec.read_string()?;
+        $T::from_str(&change).map_err(|_| ProtError::InvalidEnumValue(stringify!($T), change.clone()))?;
         Ok($C {
-            change: codec.read_string()?,
+            change,
             node: codec.read_inet()?,
         })
     }
@@ -93,7 +95,7 @@ impl SchemaChange {
     }
 
     pub fn target(&self) -> SchemaChangeTarget {
-        FromStr::from_str(&self.change).unwrap()
+        FromStr::from_str(&self.target).unwrap()
     }
 
     pub fn keyspace(&self) -> &str {
@@ -110,11 +112,11 @@ impl SchemaChange {
 }
 
 impl Serializable for SchemaChange {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.change.length() + self.target.length() + self.ks.length() + self.name.length() + self.args.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_string(&self.change)?;
         codec.write_string(&self.target)?;
         codec.write_string(&self.ks)?;
@@ -127,14 +129,17 @@ impl Serializable for SchemaChange {
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
         let change = codec.read_string()?;
         let target = codec.read_string()?;
         let ks = codec.read_string()?;
         let mut name = None;
         let mut args = None;
 
-        let target_type = FromStr::from_str(&target).unwrap();
+        SchemaChangeType::from_str(&change)
+            .map_err(|_| ProtError::InvalidEnumValue("SchemaChangeType", change.clone()))?;
+        let target_type = SchemaChangeTarget::from_str(&target)
+            .map_err(|_| ProtError::InvalidEnumValue("SchemaChangeTarget", target.clone()))?;
         match target_type {
             SchemaChangeTarget::Keyspace => (),
             SchemaChangeTarget::Table | SchemaChangeTarget::Type => {
@@ -169,25 +174,25 @@ macro_rules! match_event_type {
 }
 
 macro_rules! match_length {
-    ($sf:expr, $($E:ident),+) => (
+    ($sf:expr, $version:expr, $($E:ident),+) => (
         match $sf {
-            $(Self::$E(ref e) => e.length(),)*
+            $(Self::$E(ref e) => e.length($version),)*
         }
     );
 }
 
 macro_rules! match_encode {
-    ($sf:expr, $codec:expr, $($E:ident),+) => (
+    ($sf:expr, $codec:expr, $version:expr, $($E:ident),+) => (
         match $sf {
-            $(Self::$E(ref e) => e.encode($codec),)*
+            $(Self::$E(ref e) => e.encode($codec, $version),)*
         }
     );
 }
 
 macro_rules! match_decode {
-    ($event_type:expr, $codec:expr, $($E:ident),+) => (
+    ($event_type:expr, $codec:expr, $version:expr, $($E:ident),+) => (
         match $event_type {
-            $(EventType::$E => Event::$E($E::decode($codec)?),)*
+            $(EventType::$E => Event::$E($E::decode($codec, $version)?),)*
         }
     );
 }
@@ -204,20 +209,21 @@ impl Message for Event {
 }
 
 impl Serializable for Event {
-    fn length(&self) -> u32 {
+    fn length(&self, version: Version) -> u32 {
         let event_type = match_event_type!(self, TopologyChange, StatusChange, SchemaChange);
-        event_type.to_string().length() + match_length!(self, TopologyChange, StatusChange, SchemaChange)
+        event_type.to_string().length() + match_length!(self, version, TopologyChange, StatusChange, SchemaChange)
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
         let event_type = match_event_type!(self, TopologyChange, StatusChange, SchemaChange);
         codec.write_string(&event_type.to_string())?;
-        match_encode!(self, codec, TopologyChange, StatusChange, SchemaChange)
+        match_encode!(self, codec, version, TopologyChange, StatusChange, SchemaChange)
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
-        let event_type = FromStr::from_str(&codec.read_string()?).unwrap();
-        let event = match_decode!(event_type, codec, TopologyChange, StatusChange, SchemaChange);
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> {
+        let v = codec.read_string()?;
+        let event_type = EventType::from_str(&v).map_err(|_| ProtError::UnknownEventType(v.clone()))?;
+        let event = match_decode!(event_type, codec, version, TopologyChange, StatusChange, SchemaChange);
         Ok(event)
     }
 }
@@ -3,6 +3,7 @@ use crate::def::*;
 use crate::message::*;
 use crate::response::event::SchemaChange;
 use crate::result::*;
+use crate::row::FromRow;
 use crate::types::*;
 
 use num_traits::FromPrimitive;
@@ -18,11 +19,11 @@ const EMPTY_COL_SPECS: Vec<ColSpec> = Vec::new();
 pub struct Void {}
 
 impl Serializable for Void {
-    fn length(&self) -> u32 { 0 }
+    fn length(&self, _version: Version) -> u32 { 0 }
 
-    fn encode<B: io::Read + io::Write>(&self, _codec: &mut Codec<B>) -> ProtResult<()> { OK }
+    fn encode<B: io::Read + io::Write>(&self, _codec: &mut Codec<B>, _version: Version) -> ProtResult<()> { OK }
 
-    fn decode<B: io::Read + io::Write>(_codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(_codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
         Ok(Void {})
     }
 }
@@ -43,17 +44,17 @@ impl GlobalTableSpec {
 }
 
 impl Serializable for GlobalTableSpec {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.keyspace.length() + self.table.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_string(&self.keyspace)?;
         codec.write_string(&self.table)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
         Ok(GlobalTableSpec::new(&codec.read_string()?, &codec.read_string()?))
     }
 }
@@ -95,7 +96,7 @@ impl ColSpec {
         let ks_table = if is_global_table_spec {
             None
         } else {
-            Some(GlobalTableSpec::decode(codec)?)
+            Some(GlobalTableSpec::decode(codec, Version::default())?)
         };
 
         Ok(ColSpec {
@@ -107,24 +108,24 @@ impl ColSpec {
 }
 
 impl Serializable for ColSpec {
-    fn length(&self) -> u32 {
+    fn length(&self, version: Version) -> u32 {
         let mut len = 0;
         if let Some(ref ks_table) = self.ks_table {
-            len += ks_table.length();
+            len += ks_table.length(version);
         }
         len + self.name.length() + self.ty.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
         if let Some(ref ks_table) = self.ks_table {
-            ks_table.encode(codec)?;
+            ks_table.encode(codec, version)?;
         }
         codec.write_string(&self.name)?;
         codec.write_option(&self.ty)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(_codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(_codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
         unimplemented!()
     }
 }
@@ -134,6 +135,7 @@ pub struct RowsMetadata {
     no_metadata: bool,
     paging_state: Bytes,
     new_metadata_id: Option<ShortBytes>,
+    columns_count: Int,
     ks_table: Option<GlobalTableSpec>,
     col_specs: Vec<ColSpec>,
 }
@@ -155,10 +157,19 @@ impl RowsMetadata {
         self.ks_table = Some(global_table_spec);
     }
 
+    pub fn set_columns_count(&mut self, columns_count: Int) {
+        self.columns_count = columns_count;
+    }
+
     pub fn set_col_specs(&mut self, col_spec: Vec<ColSpec>) {
+        self.columns_count = col_spec.len() as Int;
         self.col_specs = col_spec;
     }
 
+    pub fn is_no_metadata(&self) -> bool {
+        self.no_metadata
+    }
+
     pub fn paging_state(&self) -> &Bytes {
         &self.paging_state
     }
@@ -167,6 +178,10 @@ impl RowsMetadata {
         &self.new_metadata_id
     }
 
+    pub fn columns_count(&self) -> Int {
+        self.columns_count
+    }
+
     pub fn global_table_spec(&self) -> &Option<GlobalTableSpec> {
         &self.ks_table
     }
@@ -179,7 +194,7 @@ impl RowsMetadata {
         &self.col_specs[i].ty
     }
 
-    fn flags(&self) -> Int {
+    fn flags(&self, version: Version) -> Int {
         let mut flags = 0;
         if self.ks_table.is_some() {
             flags |= RowsFlags::GlobalTablesSpec;
@@ -190,7 +205,7 @@ impl RowsMetadata {
         if self.no_metadata {
             flags |= RowsFlags::NoMetadata;
         }
-        if self.new_metadata_id.is_some() {
+        if self.new_metadata_id.is_some() && version >= Version::V5 {
             flags |= RowsFlags::MetadataChanged;
         }
         flags
@@ -198,24 +213,34 @@ impl RowsMetadata {
 }
 
 impl Serializable for RowsMetadata {
-    fn length(&self) -> u32 {
-        let mut len = len::INT * 2 + self.paging_state.length();
-        if let Some(ref new_metadata_id) = self.new_metadata_id {
-            len += new_metadata_id.length();
+    fn length(&self, version: Version) -> u32 {
+        let mut len = len::INT * 2;
+        if self.paging_state.is_some() {
+            len += self.paging_state.length();
+        }
+        if self.new_metadata_id.is_some() && version >= Version::V5 {
+            len += self.new_metadata_id.length();
         }
+
+        if self.no_metadata {
+            return len;
+        }
+
         if let Some(ref ks_table) = self.ks_table {
-            len += ks_table.length();
+            len += ks_table.length(version);
         }
-        self.col_specs.iter().fold(len, |len, e| len + e.length());
-        len
+        self.col_specs.iter().fold(len, |len, e| len + e.length(version))
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
-        codec.write_int(self.flags())?;
-        codec.write_int(self.col_specs.len() as Int)?;
-        codec.write_bytes(&self.paging_state)?;
-        if let Some(ref new_metadata_id) = self.new_metadata_id {
-            codec.write_short_bytes(&new_metadata_id)?;
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
+        let flags = self.flags(version);
+        codec.write_int(flags)?;
+        codec.write_int(self.columns_count)?;
+        if self.paging_state.is_some() {
+            codec.write_bytes(&self.paging_state)?;
+        }
+        if RowsFlags::MetadataChanged.is_set(flags) {
+            codec.write_short_bytes(self.new_metadata_id.as_ref().unwrap())?;
         }
 
         if self.no_metadata {
@@ -223,24 +248,25 @@ impl Serializable for RowsMetadata {
         }
 
         if let Some(ref ks_table) = self.ks_table {
-            ks_table.encode(codec)?;
+            ks_table.encode(codec, version)?;
         }
         for col_spec in &self.col_specs {
-            col_spec.encode(codec)?;
+            col_spec.encode(codec, version)?;
         }
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> where Self: Sized {
         let flags = codec.read_int()?;
         let col_specs_len = codec.read_int()?;
+        codec.check_count(i64::from(col_specs_len))?;
 
         let paging_state = if RowsFlags::HasMorePages.is_set(flags) {
             codec.read_bytes()?
         } else {
             None
         };
-        let new_metadata_id = if RowsFlags::MetadataChanged.is_set(flags) {
+        let new_metadata_id = if RowsFlags::MetadataChanged.is_set(flags) && version >= Version::V5 {
             Some(codec.read_short_bytes()?)
         } else {
             None
@@ -251,6 +277,7 @@ impl Serializable for RowsMetadata {
                 no_metadata: true,
                 paging_state,
                 new_metadata_id,
+                columns_count: col_specs_len,
                 ks_table: None,
                 col_specs: EMPTY_COL_SPECS,
             });
@@ -258,7 +285,7 @@ impl Serializable for RowsMetadata {
 
         let is_global_table_spec = RowsFlags::GlobalTablesSpec.is_set(flags);
         let ks_table = if is_global_table_spec {
-            Some(GlobalTableSpec::decode(codec)?)
+            Some(GlobalTableSpec::decode(codec, version)?)
         } else {
             None
         };
@@ -270,6 +297,7 @@ impl Serializable for RowsMetadata {
             no_metadata: false,
             paging_state,
             new_metadata_id,
+            columns_count: col_specs_len,
             ks_table,
             col_specs,
         })
@@ -294,11 +322,24 @@ impl Rows {
         &self.metadata
     }
 
+    pub fn content(&self) -> &Vec<Vec<Bytes>> {
+        &self.content
+    }
+
+    pub fn rows_as<T: FromRow>(&self) -> ProtResult<Vec<T>> {
+        self.content.iter().map(|row| T::from_row(&self.metadata.col_specs, row)).collect()
+    }
+
+    pub fn into_parts(self) -> (RowsMetadata, Vec<Vec<Bytes>>) {
+        (self.metadata, self.content)
+    }
+
     fn content_length(&self) -> u32 {
-        self.content.iter().fold(0, |len, e| len + e.iter().fold(0, |len, e| len + e.length()))
+        self.content.iter().fold(len::INT, |len, e| len + e.iter().fold(0, |len, e| len + e.length()))
     }
 
     fn encode_content<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+        codec.write_int(self.content.len() as Int)?;
         for row in &self.content {
             for col in row {
                 codec.write_bytes(col)?;
@@ -322,20 +363,21 @@ impl Rows {
 }
 
 impl Serializable for Rows {
-    fn length(&self) -> u32 {
-        self.metadata.length() + self.content_length()
+    fn length(&self, version: Version) -> u32 {
+        self.metadata.length(version) + self.content_length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
-        self.metadata.encode(codec)?;
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
+        self.metadata.encode(codec, version)?;
         self.encode_content(codec)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
-        let metadata = RowsMetadata::decode(codec)?;
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> where Self: Sized {
+        let metadata = RowsMetadata::decode(codec, version)?;
         let row_len = codec.read_int()?;
-        let content = Rows::decode_content(codec, row_len, metadata.col_specs().len() as Int)?;
+        codec.check_count(i64::from(row_len))?;
+        let content = Rows::decode_content(codec, row_len, metadata.columns_count())?;
         Ok(Rows {
             metadata,
             content,
@@ -364,8 +406,10 @@ impl Debug for Rows {
                 }
 
                 let col_type = self.metadata.col_type(i);
-                let v = unmarshal(col_type, col.as_ref().unwrap()).unwrap();
-                write!(f, "{:?}", v)?;
+                match unmarshal(col_type, col.as_ref().unwrap()) {
+                    Ok(v) => write!(f, "{:?}", v)?,
+                    Err(_) => write!(f, "{:?}", col.as_ref().unwrap())?,
+                }
             }
             write!(f, "]\n")?;
         }
@@ -388,15 +432,15 @@ impl SetKeyspace {
 }
 
 impl Serializable for SetKeyspace {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.ks.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_string(&self.ks)
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> where Self: Sized {
         Ok(SetKeyspace {
             ks: codec.read_string()?,
         })
@@ -445,45 +489,53 @@ impl PreparedMetadata {
 }
 
 impl Serializable for PreparedMetadata {
-    fn length(&self) -> u32 {
-        let mut len = len::INT * 3 + len::SHORT * self.pk_indices.len() as u32;
+    fn length(&self, version: Version) -> u32 {
+        let mut len = len::INT * 2;
+        if version >= Version::V4 {
+            len += len::INT + len::SHORT * self.pk_indices.len() as u32;
+        }
         if let Some(ref ks_table) = self.ks_table {
-            len += ks_table.length();
+            len += ks_table.length(version);
         }
-        self.col_specs.iter().fold(len, |len, e| len + e.length());
-        len
+        self.col_specs.iter().fold(len, |len, e| len + e.length(version))
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
         codec.write_int(self.flags())?;
         codec.write_int(self.col_specs.len() as Int)?;
-        codec.write_int(self.pk_indices.len() as Int)?;
 
-        for pk_index in &self.pk_indices {
-            codec.write_short(*pk_index)?;
+        if version >= Version::V4 {
+            codec.write_int(self.pk_indices.len() as Int)?;
+            for pk_index in &self.pk_indices {
+                codec.write_short(*pk_index)?;
+            }
         }
         if let Some(ref ks_table) = self.ks_table {
-            ks_table.encode(codec)?;
+            ks_table.encode(codec, version)?;
         }
         for col_spec in &self.col_specs {
-            col_spec.encode(codec)?;
+            col_spec.encode(codec, version)?;
         }
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> where Self: Sized {
         let flags = codec.read_int()?;
         let col_specs_len = codec.read_int()?;
-        let pk_indices_len = codec.read_int()?;
+        codec.check_count(i64::from(col_specs_len))?;
 
         let mut pk_indices = Vec::new();
-        for _ in 1..=pk_indices_len {
-            pk_indices.push(codec.read_short()?);
+        if version >= Version::V4 {
+            let pk_indices_len = codec.read_int()?;
+            codec.check_count(i64::from(pk_indices_len))?;
+            for _ in 1..=pk_indices_len {
+                pk_indices.push(codec.read_short()?);
+            }
         }
 
         let is_global_table_spec = PreparedFlags::GlobalTablesSpec.is_set(flags);
         let ks_table = if is_global_table_spec {
-            Some(GlobalTableSpec::decode(codec)?)
+            Some(GlobalTableSpec::decode(codec, version)?)
         } else {
             None
         };
@@ -504,6 +556,7 @@ impl Serializable for PreparedMetadata {
 #[derive(Debug, Default, PartialEq)]
 pub struct Prepared {
     id: ShortBytes,
+    result_metadata_id: ShortBytes,
     metadata: PreparedMetadata,
     result_metadata: RowsMetadata,
 }
@@ -512,15 +565,35 @@ impl Prepared {
     pub fn new(id: ShortBytes, metadata: PreparedMetadata, result_metadata: RowsMetadata) -> Self {
         Prepared {
             id,
+            result_metadata_id: ShortBytes::new(),
             metadata,
             result_metadata,
         }
     }
 
+    pub fn set_result_metadata_id(&mut self, result_metadata_id: ShortBytes) {
+        self.result_metadata_id = result_metadata_id;
+    }
+
+    pub fn update_result_metadata(&mut self, mut metadata: RowsMetadata) -> bool {
+        match metadata.new_metadata_id.take() {
+            Some(id) => {
+                self.result_metadata_id = id;
+                self.result_metadata = metadata;
+                true
+            },
+            None => false,
+        }
+    }
+
     pub fn id(&self) -> &ShortBytes {
         &self.id
     }
 
+    pub fn result_metadata_id(&self) -> &ShortBytes {
+        &self.result_metadata_id
+    }
+
     pub fn metadata(&self) -> &PreparedMetadata {
         &self.metadata
     }
@@ -531,24 +604,37 @@ impl Prepared {
 }
 
 impl Serializable for Prepared {
-    fn length(&self) -> u32 {
-        self.id.length() + self.metadata.length() + self.result_metadata.length()
+    fn length(&self, version: Version) -> u32 {
+        let mut len = self.id.length() + self.metadata.length(version) + self.result_metadata.length(version);
+        if version >= Version::V5 {
+            len += self.result_metadata_id.length();
+        }
+        len
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
         codec.write_short_bytes(&self.id)?;
-        self.metadata.encode(codec)?;
-        self.result_metadata.encode(codec)?;
+        if version >= Version::V5 {
+            codec.write_short_bytes(&self.result_metadata_id)?;
+        }
+        self.metadata.encode(codec, version)?;
+        self.result_metadata.encode(codec, version)?;
         OK
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> where Self: Sized {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> where Self: Sized {
         let id = codec.read_short_bytes()?;
-        let metadata = PreparedMetadata::decode(codec)?;
-        let result_metadata = RowsMetadata::decode(codec)?;
+        let result_metadata_id = if version >= Version::V5 {
+            codec.read_short_bytes()?
+        } else {
+            ShortBytes::new()
+        };
+        let metadata = PreparedMetadata::decode(codec, version)?;
+        let result_metadata = RowsMetadata::decode(codec, version)?;
 
         Ok(Prepared {
             id,
+            result_metadata_id,
             metadata,
             result_metadata,
         })
@@ -573,25 +659,25 @@ macro_rules! match_result_kind {
 }
 
 macro_rules! match_length {
-    ($sf:expr, $($R:ident),+) => (
+    ($sf:expr, $version:expr, $($R:ident),+) => (
         match $sf {
-            $(ResultBody::$R(ref r) => r.length(),)*
+            $(ResultBody::$R(ref r) => r.length($version),)*
         }
     );
 }
 
 macro_rules! match_encode {
-    ($sf:expr, $codec:expr, $($R:ident),+) => (
+    ($sf:expr, $codec:expr, $version:expr, $($R:ident),+) => (
         match $sf {
-            $(ResultBody::$R(ref r) => r.encode($codec),)*
+            $(ResultBody::$R(ref r) => r.encode($codec, $version),)*
         }
     );
 }
 
 macro_rules! match_decode {
-    ($event_type:expr, $codec:expr, $($R:ident),+) => (
+    ($event_type:expr, $codec:expr, $version:expr, $($R:ident),+) => (
         match $event_type {
-            $(ResultKind::$R => ResultBody::$R($R::decode($codec)?),)*
+            $(ResultKind::$R => ResultBody::$R($R::decode($codec, $version)?),)*
         }
     );
 }
@@ -641,19 +727,20 @@ impl Message for Result {
 }
 
 impl Serializable for Result {
-    fn length(&self) -> u32 {
-        len::INT + match_length!(self.body, Void, Rows, SetKeyspace, Prepared, SchemaChange)
+    fn length(&self, version: Version) -> u32 {
+        len::INT + match_length!(self.body, version, Void, Rows, SetKeyspace, Prepared, SchemaChange)
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, version: Version) -> ProtResult<()> {
         let result_kind = match_result_kind!(self.body, Void, Rows, SetKeyspace, Prepared, SchemaChange);
         codec.write_int(result_kind as Int)?;
-        match_encode!(self.body, codec, Void, Rows, SetKeyspace, Prepared, SchemaChange)
+        match_encode!(self.body, codec, version, Void, Rows, SetKeyspace, Prepared, SchemaChange)
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
-        let result_kind = FromPrimitive::from_i32(codec.read_int()?).unwrap();
-        let result = match_decode!(result_kind, codec, Void, Rows, SetKeyspace, Prepared, SchemaChange);
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, version: Version) -> ProtResult<Self> {
+        let v = codec.read_int()?;
+        let result_kind = FromPrimitive::from_i32(v).ok_or(ProtError::UnknownResultKind(v))?;
+        let result = match_decode!(result_kind, codec, version, Void, Rows, SetKeyspace, Prepared, SchemaChange);
         Ok(Result::new(result))
     }
 }
@@ -38,15 +38,15 @@ impl Message for Supported {
 }
 
 impl Serializable for Supported {
-    fn length(&self) -> u32 {
+    fn length(&self, _version: Version) -> u32 {
         self.options.length()
     }
 
-    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>) -> ProtResult<()> {
+    fn encode<B: io::Read + io::Write>(&self, codec: &mut Codec<B>, _version: Version) -> ProtResult<()> {
         codec.write_sting_multimap(&self.options)
     }
 
-    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>) -> ProtResult<Self> {
+    fn decode<B: io::Read + io::Write>(codec: &mut Codec<B>, _version: Version) -> ProtResult<Self> {
         Ok(Supported {
             options: codec.read_string_multimap()?,
         })
@@ -9,6 +9,22 @@ pub enum ProtError {
     Utf8Err(string::FromUtf8Error),
     AsciiErr(ascii::FromAsciiError<Vec<u8>>),
     UuidErr(uuid::Error),
+    UnknownOpcode(u8),
+    UnknownVersion(u8),
+    UnsupportedVersion(&'static str, u8),
+    InvalidEnumValue(&'static str, String),
+    InvalidLength(i64),
+    UnknownResultKind(i32),
+    UnknownEventType(String),
+    UnknownErrorCode(i32),
+    LimitExceeded(&'static str, u64),
+    TypeMismatch(&'static str, String),
+    UnexpectedNull(&'static str),
+    MissingField(String),
+    UnknownField(String),
+    InvalidField(String, Box<ProtError>),
+    SerdeErr(String),
+    InvalidFormat(&'static str, String),
 }
 
 impl From<io::Error> for ProtError {
@@ -42,6 +58,22 @@ impl Error for ProtError {
             Self::Utf8Err(e) => Error::description(e),
             Self::AsciiErr(e) => Error::description(e),
             Self::UuidErr(e) => Error::description(e),
+            Self::UnknownOpcode(_) => "unknown opcode",
+            Self::UnknownVersion(_) => "unknown protocol version",
+            Self::UnsupportedVersion(..) => "unsupported protocol version",
+            Self::InvalidEnumValue(..) => "invalid enum value",
+            Self::InvalidLength(_) => "invalid length",
+            Self::UnknownResultKind(_) => "unknown result kind",
+            Self::UnknownEventType(_) => "unknown event type",
+            Self::UnknownErrorCode(_) => "unknown error code",
+            Self::LimitExceeded(..) => "decode limit exceeded",
+            Self::TypeMismatch(..) => "type mismatch",
+            Self::UnexpectedNull(_) => "unexpected null",
+            Self::MissingField(_) => "missing field",
+            Self::UnknownField(_) => "unknown field",
+            Self::InvalidField(..) => "invalid field",
+            Self::SerdeErr(_) => "serde error",
+            Self::InvalidFormat(..) => "invalid format",
         }
     }
 
@@ -55,6 +87,22 @@ impl Error for ProtError {
 impl Display for ProtError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match *self {
+            Self::UnknownOpcode(v) | Self::UnknownVersion(v) => write!(f, "{}: {:#04x}", Error::description(self), v),
+            Self::UnsupportedVersion(name, v) => write!(f, "{} for {}: {:#04x}", Error::description(self), name, v),
+            Self::InvalidEnumValue(name, ref v) => write!(f, "{} for {}: {}", Error::description(self), name, v),
+            Self::InvalidLength(v) => write!(f, "{}: {}", Error::description(self), v),
+            Self::UnknownResultKind(v) | Self::UnknownErrorCode(v) => {
+                write!(f, "{}: {:#06x}", Error::description(self), v)
+            },
+            Self::UnknownEventType(ref v) => write!(f, "{}: {}", Error::description(self), v),
+            Self::LimitExceeded(name, v) => write!(f, "{} for {}: {}", Error::description(self), name, v),
+            Self::TypeMismatch(name, ref v) => write!(f, "{} for {}: {}", Error::description(self), name, v),
+            Self::UnexpectedNull(name) => write!(f, "{} for {}", Error::description(self), name),
+            Self::MissingField(ref name) => write!(f, "{}: {}", Error::description(self), name),
+            Self::UnknownField(ref name) => write!(f, "{}: {}", Error::description(self), name),
+            Self::InvalidField(ref name, ref e) => write!(f, "{} {}: {}", Error::description(self), name, e),
+            Self::SerdeErr(ref v) => write!(f, "{}: {}", Error::description(self), v),
+            Self::InvalidFormat(name, ref v) => write!(f, "{} for {}: {}", Error::description(self), name, v),
             _ => write!(f, "{}", Error::description(self)),
         }
     }
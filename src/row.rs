@@ -0,0 +1,41 @@
+use crate::response::result::ColSpec;
+use crate::result::*;
+use crate::types::*;
+
+#[cfg(feature = "derive")]
+pub use cql_derive::{FromRow, IntoValues};
+
+pub trait FromRow: Sized {
+    fn from_row(col_specs: &[ColSpec], row: &[Bytes]) -> ProtResult<Self>;
+}
+
+pub trait IntoValues {
+    fn to_values(&self, col_specs: &[ColSpec]) -> ProtResult<Vec<Value>>;
+}
+
+fn invalid_column(name: &str, e: ProtError) -> ProtError {
+    ProtError::InvalidField(name.to_string(), Box::new(e))
+}
+
+pub fn column<T: FromCql>(col_specs: &[ColSpec], row: &[Bytes], name: &str) -> ProtResult<T> {
+    let i = match col_specs.iter().position(|e| e.name() == name) {
+        Some(i) if i < row.len() => i,
+        _ => return Err(ProtError::MissingField(name.to_string())),
+    };
+    T::from_cql(col_specs[i].ty(), &row[i]).map_err(|e| invalid_column(name, e))
+}
+
+pub fn bind(col_specs: &[ColSpec], values: &[(&str, &dyn ToCql)]) -> ProtResult<Vec<Value>> {
+    let row = col_specs.iter().map(|col_spec| {
+        let name = col_spec.name();
+        match values.iter().find(|(e, _)| *e == name) {
+            Some((_, value)) => value.to_cql(col_spec.ty()).map_err(|e| invalid_column(name, e)),
+            None => Err(ProtError::MissingField(name.to_string())),
+        }
+    }).collect::<ProtResult<Vec<_>>>()?;
+
+    match values.iter().find(|(name, _)| col_specs.iter().all(|e| e.name() != *name)) {
+        Some((name, _)) => Err(ProtError::UnknownField(name.to_string())),
+        None => Ok(row),
+    }
+}
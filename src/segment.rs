@@ -0,0 +1,339 @@
+use crate::compression::*;
+
+use std::io::{self, Cursor};
+
+pub const MAX_PAYLOAD_LENGTH: usize = 128 * 1024 - 1;
+pub const MAX_MESSAGE_LENGTH: usize = 256 * 1024 * 1024;
+
+const ENVELOPE_HEADER_LENGTH: usize = 9;
+const ENVELOPE_LENGTH_OFFSET: usize = 5;
+
+const HEADER_LENGTH: usize = 3;
+const COMPRESSED_HEADER_LENGTH: usize = 5;
+const HEADER_CRC_LENGTH: usize = 3;
+const PAYLOAD_CRC_LENGTH: usize = 4;
+
+const LENGTH_BITS: u32 = 17;
+const LENGTH_MASK: u64 = (1 << LENGTH_BITS) - 1;
+
+const CRC24_INIT: u32 = 0x0087_5060;
+const CRC24_POLY: u32 = 0x0197_4F0B;
+const CRC32_INITIAL_BYTES: [u8; 4] = [0xFA, 0x2D, 0x55, 0xCA];
+
+pub fn crc24(mut v: u64, len: usize) -> u32 {
+    let mut crc = CRC24_INIT;
+    for _ in 0..len {
+        crc ^= ((v & 0xFF) as u32) << 16;
+        v >>= 8;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x0100_0000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc
+}
+
+pub fn crc32(v: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&CRC32_INITIAL_BYTES);
+    hasher.update(v);
+    hasher.finalize()
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn to_le(v: u64, len: usize) -> Vec<u8> {
+    (0..len).map(|i| (v >> (8 * i)) as u8).collect()
+}
+
+fn from_le(v: &[u8]) -> u64 {
+    v.iter().rev().fold(0, |acc, b| (acc << 8) | u64::from(*b))
+}
+
+fn envelope_length(v: &[u8]) -> Option<usize> {
+    if v.len() < ENVELOPE_HEADER_LENGTH {
+        return None;
+    }
+
+    let len = v[ENVELOPE_LENGTH_OFFSET..ENVELOPE_HEADER_LENGTH].iter().fold(0, |acc, b| (acc << 8) | *b as usize);
+    Some(ENVELOPE_HEADER_LENGTH + len)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Segment {
+    payload: Vec<u8>,
+    self_contained: bool,
+}
+
+impl Segment {
+    pub fn new(payload: Vec<u8>, self_contained: bool) -> Segment {
+        Segment {
+            payload,
+            self_contained,
+        }
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn into_payload(self) -> Vec<u8> {
+        self.payload
+    }
+
+    pub fn is_self_contained(&self) -> bool {
+        self.self_contained
+    }
+}
+
+pub struct SegmentCodec {
+    compressor: Option<Box<dyn Compressor>>,
+    buf: Vec<u8>,
+}
+
+impl SegmentCodec {
+    pub fn new(compressor: Option<Box<dyn Compressor>>) -> SegmentCodec {
+        SegmentCodec {
+            compressor,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn encode<W: io::Write>(&mut self, w: &mut W, segment: &Segment) -> io::Result<()> {
+        let payload = segment.payload();
+        if payload.len() > MAX_PAYLOAD_LENGTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "segment payload too large"));
+        }
+
+        let self_contained = if segment.is_self_contained() { 1 } else { 0 };
+        match self.compressor {
+            Some(ref mut compressor) => {
+                self.buf.clear();
+                compressor.compress_segment(payload, &mut self.buf)?;
+                let (payload, uncompressed_len) = if self.buf.len() < payload.len() {
+                    (self.buf.as_slice(), payload.len() as u64)
+                } else {
+                    (payload, 0)
+                };
+                let header = payload.len() as u64
+                    | uncompressed_len << LENGTH_BITS
+                    | self_contained << (2 * LENGTH_BITS);
+                Self::encode_header(w, header, COMPRESSED_HEADER_LENGTH)?;
+                Self::encode_payload(w, payload)
+            },
+            None => {
+                let header = payload.len() as u64 | self_contained << LENGTH_BITS;
+                Self::encode_header(w, header, HEADER_LENGTH)?;
+                Self::encode_payload(w, payload)
+            },
+        }
+    }
+
+    pub fn decode<R: io::Read>(&mut self, r: &mut R) -> io::Result<Segment> {
+        match self.compressor {
+            Some(ref mut compressor) => {
+                let header = Self::decode_header(r, COMPRESSED_HEADER_LENGTH)?;
+                let len = (header & LENGTH_MASK) as usize;
+                let uncompressed_len = (header >> LENGTH_BITS & LENGTH_MASK) as usize;
+                let self_contained = header >> (2 * LENGTH_BITS) & 1 == 1;
+
+                let payload = Self::decode_payload(r, len)?;
+                let payload = if uncompressed_len == 0 {
+                    payload
+                } else {
+                    let mut v = Vec::with_capacity(uncompressed_len);
+                    compressor.decompress_segment(&payload, uncompressed_len, &mut v)?;
+                    if v.len() != uncompressed_len {
+                        return Err(invalid_data("segment uncompressed length mismatch"));
+                    }
+                    v
+                };
+                Ok(Segment::new(payload, self_contained))
+            },
+            None => {
+                let header = Self::decode_header(r, HEADER_LENGTH)?;
+                let len = (header & LENGTH_MASK) as usize;
+                let self_contained = header >> LENGTH_BITS & 1 == 1;
+
+                let payload = Self::decode_payload(r, len)?;
+                Ok(Segment::new(payload, self_contained))
+            },
+        }
+    }
+
+    fn encode_header<W: io::Write>(w: &mut W, header: u64, len: usize) -> io::Result<()> {
+        w.write_all(&to_le(header, len))?;
+        w.write_all(&to_le(u64::from(crc24(header, len)), HEADER_CRC_LENGTH))
+    }
+
+    fn encode_payload<W: io::Write>(w: &mut W, payload: &[u8]) -> io::Result<()> {
+        w.write_all(payload)?;
+        w.write_all(&to_le(u64::from(crc32(payload)), PAYLOAD_CRC_LENGTH))
+    }
+
+    fn decode_header<R: io::Read>(r: &mut R, len: usize) -> io::Result<u64> {
+        let mut v = vec![0; len + HEADER_CRC_LENGTH];
+        r.read_exact(&mut v)?;
+
+        let header = from_le(&v[..len]);
+        if from_le(&v[len..]) != u64::from(crc24(header, len)) {
+            return Err(invalid_data("segment header crc24 mismatch"));
+        }
+        Ok(header)
+    }
+
+    fn decode_payload<R: io::Read>(r: &mut R, len: usize) -> io::Result<Vec<u8>> {
+        let mut v = vec![0; len + PAYLOAD_CRC_LENGTH];
+        r.read_exact(&mut v)?;
+
+        let crc = from_le(&v[len..]);
+        v.truncate(len);
+        if crc != u64::from(crc32(&v)) {
+            return Err(invalid_data("segment payload crc32 mismatch"));
+        }
+        Ok(v)
+    }
+}
+
+pub struct SegmentIo<B: io::Read + io::Write> {
+    io: B,
+    codec: Option<SegmentCodec>,
+    rbuf: Cursor<Vec<u8>>,
+    wbuf: Vec<u8>,
+    max_payload_length: usize,
+    max_message_length: usize,
+}
+
+impl<B> SegmentIo<B> where B: io::Read + io::Write {
+    pub fn new(io: B) -> SegmentIo<B> {
+        SegmentIo {
+            io,
+            codec: None,
+            rbuf: Cursor::new(Vec::new()),
+            wbuf: Vec::new(),
+            max_payload_length: MAX_PAYLOAD_LENGTH,
+            max_message_length: MAX_MESSAGE_LENGTH,
+        }
+    }
+
+    pub fn max_payload_length(&self) -> usize {
+        self.max_payload_length
+    }
+
+    pub fn set_max_payload_length(&mut self, len: usize) {
+        self.max_payload_length = len.clamp(1, MAX_PAYLOAD_LENGTH);
+    }
+
+    pub fn max_message_length(&self) -> usize {
+        self.max_message_length
+    }
+
+    pub fn set_max_message_length(&mut self, len: usize) {
+        self.max_message_length = len;
+    }
+
+    pub fn enable(&mut self, compressor: Option<Box<dyn Compressor>>) {
+        self.codec = Some(SegmentCodec::new(compressor));
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.codec.is_some()
+    }
+
+    pub fn get_ref(&self) -> &B {
+        &self.io
+    }
+
+    pub fn get_mut(&mut self) -> &mut B {
+        &mut self.io
+    }
+
+    fn is_drained(&self) -> bool {
+        self.rbuf.position() as usize >= self.rbuf.get_ref().len()
+    }
+
+    fn read_segments(&mut self) -> io::Result<()> {
+        let codec = self.codec.as_mut().unwrap();
+        let segment = codec.decode(&mut self.io)?;
+        if segment.is_self_contained() {
+            self.rbuf = Cursor::new(segment.into_payload());
+            return Ok(());
+        }
+
+        let mut v = segment.into_payload();
+        loop {
+            if let Some(len) = envelope_length(&v) {
+                if len > self.max_message_length {
+                    return Err(invalid_data("message too large"));
+                }
+                if v.len() > len {
+                    return Err(invalid_data("segment exceeds message boundary"));
+                }
+                if v.len() == len {
+                    break;
+                }
+            }
+
+            let segment = codec.decode(&mut self.io)?;
+            if segment.is_self_contained() {
+                return Err(invalid_data("self-contained segment inside a split message"));
+            }
+            v.extend_from_slice(segment.payload());
+        }
+
+        self.rbuf = Cursor::new(v);
+        Ok(())
+    }
+
+    fn write_segments(&mut self) -> io::Result<()> {
+        let codec = self.codec.as_mut().unwrap();
+        let v = self.wbuf.split_off(0);
+        if v.len() > self.max_message_length {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "message too large"));
+        }
+
+        if v.len() <= self.max_payload_length {
+            return codec.encode(&mut self.io, &Segment::new(v, true));
+        }
+        for chunk in v.chunks(self.max_payload_length) {
+            codec.encode(&mut self.io, &Segment::new(chunk.to_vec(), false))?;
+        }
+        Ok(())
+    }
+}
+
+impl<B> io::Read for SegmentIo<B> where B: io::Read + io::Write {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.codec.is_none() {
+            return self.io.read(buf);
+        }
+
+        if self.is_drained() {
+            self.read_segments()?;
+        }
+        io::Read::read(&mut self.rbuf, buf)
+    }
+}
+
+impl<B> io::Write for SegmentIo<B> where B: io::Read + io::Write {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.codec {
+            Some(_) => {
+                self.wbuf.extend_from_slice(buf);
+                Ok(buf.len())
+            },
+            None => self.io.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.codec.is_some() && !self.wbuf.is_empty() {
+            self.write_segments()?;
+        }
+        self.io.flush()
+    }
+}
@@ -0,0 +1,420 @@
+use crate::codec::*;
+use crate::result::*;
+use crate::types::*;
+
+use bigdecimal::BigDecimal;
+use chrono::prelude::*;
+use num::BigInt;
+use serde::ser::{self, Impossible, Serialize};
+
+use std::{
+    convert::TryFrom,
+    fmt::Display,
+    io::Cursor,
+    net::IpAddr,
+    str::FromStr,
+};
+
+impl ser::Error for ProtError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ProtError::SerdeErr(msg.to_string())
+    }
+}
+
+pub fn to_value<T: Serialize + ?Sized>(v: &T, ty: &Opt) -> ProtResult<Value> {
+    Ok(Value::from(v.serialize(Serializer::new(ty))?))
+}
+
+fn out_of_range<T: Display>(v: T, ty: &Opt) -> ProtError {
+    ProtError::SerdeErr(format!("{} out of range for {:?}", v, ty.id))
+}
+
+fn parse<T: FromStr>(v: &str) -> ProtResult<T> where T::Err: Display {
+    v.parse().map_err(|e: T::Err| ProtError::SerdeErr(e.to_string()))
+}
+
+fn narrow<T: TryFrom<i64>>(v: i64, ty: &Opt) -> ProtResult<T> {
+    T::try_from(v).map_err(|_| out_of_range(v, ty))
+}
+
+fn encode_elements(count: Option<usize>, elements: &[Bytes]) -> ProtResult<Bytes> {
+    let mut encoder = Encoder::new(Cursor::new(Vec::new()));
+    if let Some(count) = count {
+        encoder.write_int(count as Int)?;
+    }
+    for e in elements {
+        encoder.write_bytes(e)?;
+    }
+    Ok(Some(encoder.into_io().into_inner()))
+}
+
+pub struct Serializer<'a> {
+    ty: &'a Opt,
+}
+
+impl<'a> Serializer<'a> {
+    pub fn new(ty: &'a Opt) -> Serializer<'a> {
+        Serializer {
+            ty,
+        }
+    }
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = Bytes;
+    type Error = ProtError;
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = SeqSerializer<'a>;
+    type SerializeTupleVariant = Impossible<Bytes, ProtError>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = MapSerializer<'a>;
+    type SerializeStructVariant = Impossible<Bytes, ProtError>;
+
+    fn serialize_bool(self, v: bool) -> ProtResult<Bytes> {
+        match self.ty.id {
+            OptIds::Boolean => marshal_boolean(&v),
+            _ => Err(type_mismatch::<bool>(self.ty)),
+        }
+    }
+
+    fn serialize_i8(self, v: i8) -> ProtResult<Bytes> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> ProtResult<Bytes> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> ProtResult<Bytes> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> ProtResult<Bytes> {
+        match self.ty.id {
+            OptIds::Tinyint => marshal_tinyint(&narrow(v, self.ty)?),
+            OptIds::Smallint => marshal_smallint(&narrow(v, self.ty)?),
+            OptIds::Int => marshal_int(&narrow(v, self.ty)?),
+            OptIds::Bigint => marshal_bigint(&v),
+            OptIds::Counter => marshal_counter(&v),
+            OptIds::Varint => marshal_varint(&BigInt::from(v)),
+            OptIds::Decimal => marshal_decimal(&BigDecimal::from(v)),
+            OptIds::Timestamp => {
+                marshal_timestamp(&Utc.timestamp_millis_opt(v).single().ok_or_else(|| out_of_range(v, self.ty))?)
+            },
+            _ => Err(type_mismatch::<i64>(self.ty)),
+        }
+    }
+
+    fn serialize_u8(self, v: u8) -> ProtResult<Bytes> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> ProtResult<Bytes> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> ProtResult<Bytes> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> ProtResult<Bytes> {
+        match self.ty.id {
+            OptIds::Varint => marshal_varint(&BigInt::from(v)),
+            _ => {
+                let v = i64::try_from(v).map_err(|_| out_of_range(v, self.ty))?;
+                self.serialize_i64(v)
+            },
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> ProtResult<Bytes> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> ProtResult<Bytes> {
+        match self.ty.id {
+            OptIds::Float => marshal_float(&(v as f32)),
+            OptIds::Double => marshal_double(&v),
+            OptIds::Decimal => marshal_decimal(&parse(&v.to_string())?),
+            _ => Err(type_mismatch::<f64>(self.ty)),
+        }
+    }
+
+    fn serialize_char(self, v: char) -> ProtResult<Bytes> {
+        self.serialize_str(&v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> ProtResult<Bytes> {
+        match self.ty.id {
+            OptIds::Varchar => marshal_varchar(v),
+            OptIds::Ascii if v.is_ascii() => marshal_varchar(v),
+            OptIds::Uuid => marshal_uuid(&parse(v)?),
+            OptIds::Timeuuid => marshal_timeuuid(&parse(v)?),
+            OptIds::Inet => marshal_inet(&parse::<IpAddr>(v)?),
+            OptIds::Timestamp => marshal_timestamp(&parse(v)?),
+            OptIds::Date => marshal_date(&Date::from_utc(parse(v)?, Utc)),
+            OptIds::Time => marshal_time(&parse(v)?),
+            OptIds::Decimal => marshal_decimal(&parse(v)?),
+            OptIds::Varint => marshal_varint(&parse(v)?),
+            _ => Err(type_mismatch::<str>(self.ty)),
+        }
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> ProtResult<Bytes> {
+        match self.ty.id {
+            OptIds::Blob => Ok(Some(v.to_vec())),
+            _ => Err(type_mismatch::<[u8]>(self.ty)),
+        }
+    }
+
+    fn serialize_none(self) -> ProtResult<Bytes> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, v: &T) -> ProtResult<Bytes> {
+        v.serialize(self)
+    }
+
+    fn serialize_unit(self) -> ProtResult<Bytes> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> ProtResult<Bytes> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> ProtResult<Bytes> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, v: &T) -> ProtResult<Bytes> {
+        v.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _v: &T,
+    ) -> ProtResult<Bytes> {
+        Err(ProtError::TypeMismatch(name, format!("{:?}", self.ty.id)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> ProtResult<SeqSerializer<'a>> {
+        SeqSerializer::new(self.ty, len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> ProtResult<SeqSerializer<'a>> {
+        SeqSerializer::new(self.ty, Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> ProtResult<SeqSerializer<'a>> {
+        SeqSerializer::new(self.ty, Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> ProtResult<Self::SerializeTupleVariant> {
+        Err(ProtError::TypeMismatch(name, format!("{:?}", self.ty.id)))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> ProtResult<MapSerializer<'a>> {
+        MapSerializer::new(self.ty, len)
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> ProtResult<MapSerializer<'a>> {
+        MapSerializer::new(self.ty, Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> ProtResult<Self::SerializeStructVariant> {
+        Err(ProtError::TypeMismatch(name, format!("{:?}", self.ty.id)))
+    }
+}
+
+pub struct SeqSerializer<'a> {
+    ty: &'a Opt,
+    elements: Vec<Bytes>,
+}
+
+impl<'a> SeqSerializer<'a> {
+    fn new(ty: &'a Opt, len: Option<usize>) -> ProtResult<SeqSerializer<'a>> {
+        match ty.value {
+            OptValue::List(_) | OptValue::Set(_) | OptValue::Tuple(_) => Ok(SeqSerializer {
+                ty,
+                elements: Vec::with_capacity(len.unwrap_or_default()),
+            }),
+            _ => Err(ProtError::TypeMismatch("sequence", format!("{:?}", ty.id))),
+        }
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, v: &T) -> ProtResult<()> {
+        let ty = match self.ty.value {
+            OptValue::List(ref ty) | OptValue::Set(ref ty) => ty,
+            OptValue::Tuple(ref types) => match types.get(self.elements.len()) {
+                Some(ty) => ty,
+                None => return Err(out_of_range(self.elements.len() + 1, self.ty)),
+            },
+            _ => unreachable!(),
+        };
+        self.elements.push(v.serialize(Serializer::new(ty))?);
+        Ok(())
+    }
+
+    fn finish(self) -> ProtResult<Bytes> {
+        match self.ty.value {
+            OptValue::Tuple(ref types) if types.len() != self.elements.len() => {
+                Err(out_of_range(self.elements.len(), self.ty))
+            },
+            OptValue::Tuple(_) => encode_elements(None, &self.elements),
+            _ => encode_elements(Some(self.elements.len()), &self.elements),
+        }
+    }
+}
+
+impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
+    type Ok = Bytes;
+    type Error = ProtError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, v: &T) -> ProtResult<()> {
+        self.element(v)
+    }
+
+    fn end(self) -> ProtResult<Bytes> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTuple for SeqSerializer<'a> {
+    type Ok = Bytes;
+    type Error = ProtError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, v: &T) -> ProtResult<()> {
+        self.element(v)
+    }
+
+    fn end(self) -> ProtResult<Bytes> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SeqSerializer<'a> {
+    type Ok = Bytes;
+    type Error = ProtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, v: &T) -> ProtResult<()> {
+        self.element(v)
+    }
+
+    fn end(self) -> ProtResult<Bytes> {
+        self.finish()
+    }
+}
+
+pub struct MapSerializer<'a> {
+    ty: &'a Opt,
+    key: Option<(Bytes, String)>,
+    entries: Vec<(Bytes, String, Bytes)>,
+}
+
+impl<'a> MapSerializer<'a> {
+    fn new(ty: &'a Opt, len: Option<usize>) -> ProtResult<MapSerializer<'a>> {
+        match ty.value {
+            OptValue::Map(..) | OptValue::Udt(_) => Ok(MapSerializer {
+                ty,
+                key: None,
+                entries: Vec::with_capacity(len.unwrap_or_default()),
+            }),
+            _ => Err(ProtError::TypeMismatch("map", format!("{:?}", ty.id))),
+        }
+    }
+
+    fn entry_key<T: Serialize + ?Sized>(&mut self, k: &T) -> ProtResult<()> {
+        let key = match self.ty.value {
+            OptValue::Map(ref ty, _) => (k.serialize(Serializer::new(ty))?, String::new()),
+            _ => {
+                let bytes = k.serialize(Serializer::new(&Opt::new(OptIds::Varchar)))?;
+                let name = unmarshal_varchar(bytes.as_ref().unwrap_or(&Vec::new()))?;
+                (None, name)
+            },
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn entry_value<T: Serialize + ?Sized>(&mut self, v: &T) -> ProtResult<()> {
+        let (key, name) = self.key.take().ok_or_else(|| ProtError::SerdeErr("value without key".to_string()))?;
+        let ty = match self.ty.value {
+            OptValue::Map(_, ref ty) => ty,
+            OptValue::Udt(ref udt) => match udt.fields.iter().find(|(field, _)| *field == name) {
+                Some((_, ty)) => ty,
+                None => return Err(ProtError::UnknownField(name)),
+            },
+            _ => unreachable!(),
+        };
+        let value = v.serialize(Serializer::new(ty)).map_err(|e| ProtError::InvalidField(name.clone(), Box::new(e)))?;
+        self.entries.push((key, name, value));
+        Ok(())
+    }
+
+    fn finish(self) -> ProtResult<Bytes> {
+        match self.ty.value {
+            OptValue::Udt(ref udt) => {
+                let fields: Vec<Bytes> = udt.fields.iter().map(|(field, _)| {
+                    self.entries.iter().find(|(_, name, _)| name == field).and_then(|(.., value)| value.clone())
+                }).collect();
+                encode_elements(None, &fields)
+            },
+            _ => {
+                let mut v = Vec::with_capacity(self.entries.len() * 2);
+                for (key, _, value) in self.entries.iter() {
+                    v.push(key.clone());
+                    v.push(value.clone());
+                }
+                encode_elements(Some(self.entries.len()), &v)
+            },
+        }
+    }
+}
+
+impl<'a> ser::SerializeMap for MapSerializer<'a> {
+    type Ok = Bytes;
+    type Error = ProtError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, k: &T) -> ProtResult<()> {
+        self.entry_key(k)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, v: &T) -> ProtResult<()> {
+        self.entry_value(v)
+    }
+
+    fn end(self) -> ProtResult<Bytes> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStruct for MapSerializer<'a> {
+    type Ok = Bytes;
+    type Error = ProtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, k: &'static str, v: &T) -> ProtResult<()> {
+        self.entry_key(k)?;
+        self.entry_value(v)
+    }
+
+    fn end(self) -> ProtResult<Bytes> {
+        self.finish()
+    }
+}
@@ -1,4 +1,5 @@
 use crate::codec::*;
+use crate::registry;
 use crate::result::*;
 use crate::vint;
 
@@ -10,12 +11,14 @@ use num::BigInt;
 use time::Duration as StdDuration;
 
 use std::{
+    any,
     collections::{HashMap, HashSet},
+    convert::TryFrom,
     fmt::{self, Debug, Display, Formatter},
     hash::{Hash, Hasher},
     io::{Cursor, Read},
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
-    ops::Add,
+    str::FromStr,
     string::FromUtf8Error
 };
 
@@ -45,6 +48,15 @@ pub enum Value {
     NotSet,
 }
 
+impl From<Bytes> for Value {
+    fn from(bytes: Bytes) -> Value {
+        match bytes {
+            Some(v) => Value::Some(v),
+            None => Value::None,
+        }
+    }
+}
+
 pub type ShortBytes = Vec<u8>;
 
 #[derive(Clone, Copy, Debug, FromPrimitive, PartialEq)]
@@ -90,6 +102,450 @@ impl Opt {
             value: OptValue::None,
         }
     }
+
+    pub fn custom(class_name: &str) -> Opt {
+        Opt {
+            id: OptIds::Custom,
+            value: OptValue::Custom(class_name.to_string()),
+        }
+    }
+
+    pub fn vector(ty: &Opt, dimension: usize) -> Option<Opt> {
+        let class_name = class_name(ty.id)?;
+        Some(Opt::custom(&format!("{}({}{}, {})", VECTOR_TYPE, MARSHAL_PACKAGE, class_name, dimension)))
+    }
+
+    pub fn vector_type(&self) -> Option<(Opt, usize)> {
+        match self.value {
+            OptValue::Custom(ref class_name) => parse_vector_type(class_name),
+            _ => None,
+        }
+    }
+}
+
+pub const MARSHAL_PACKAGE: &str = "org.apache.cassandra.db.marshal.";
+pub const VECTOR_TYPE: &str = "org.apache.cassandra.db.marshal.VectorType";
+
+const MARSHAL_CLASSES: [(&str, OptIds); 21] = [
+    ("AsciiType", OptIds::Ascii),
+    ("LongType", OptIds::Bigint),
+    ("BytesType", OptIds::Blob),
+    ("BooleanType", OptIds::Boolean),
+    ("CounterColumnType", OptIds::Counter),
+    ("DecimalType", OptIds::Decimal),
+    ("DoubleType", OptIds::Double),
+    ("FloatType", OptIds::Float),
+    ("Int32Type", OptIds::Int),
+    ("TimestampType", OptIds::Timestamp),
+    ("DateType", OptIds::Timestamp),
+    ("UUIDType", OptIds::Uuid),
+    ("UTF8Type", OptIds::Varchar),
+    ("IntegerType", OptIds::Varint),
+    ("TimeUUIDType", OptIds::Timeuuid),
+    ("InetAddressType", OptIds::Inet),
+    ("SimpleDateType", OptIds::Date),
+    ("TimeType", OptIds::Time),
+    ("ShortType", OptIds::Smallint),
+    ("ByteType", OptIds::Tinyint),
+    ("DurationType", OptIds::Duration),
+];
+
+fn class_name(id: OptIds) -> Option<&'static str> {
+    MARSHAL_CLASSES.iter().find(|(_, e)| *e == id).map(|(class_name, _)| *class_name)
+}
+
+const CQL_TYPES: [(&str, OptIds); 21] = [
+    ("ascii", OptIds::Ascii),
+    ("bigint", OptIds::Bigint),
+    ("blob", OptIds::Blob),
+    ("boolean", OptIds::Boolean),
+    ("counter", OptIds::Counter),
+    ("decimal", OptIds::Decimal),
+    ("double", OptIds::Double),
+    ("float", OptIds::Float),
+    ("int", OptIds::Int),
+    ("timestamp", OptIds::Timestamp),
+    ("uuid", OptIds::Uuid),
+    ("text", OptIds::Varchar),
+    ("varchar", OptIds::Varchar),
+    ("varint", OptIds::Varint),
+    ("timeuuid", OptIds::Timeuuid),
+    ("inet", OptIds::Inet),
+    ("date", OptIds::Date),
+    ("time", OptIds::Time),
+    ("smallint", OptIds::Smallint),
+    ("tinyint", OptIds::Tinyint),
+    ("duration", OptIds::Duration),
+];
+
+fn udt_opt(ks: String, name: String, fields: Vec<(String, Opt)>) -> Opt {
+    Opt {
+        id: OptIds::Udt,
+        value: OptValue::Udt(OptUdt {
+            ks,
+            name,
+            fields,
+        }),
+    }
+}
+
+fn is_class_name(class_name: &str) -> bool {
+    !class_name.is_empty() && class_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '$')
+}
+
+fn split_class_args(args: &str) -> Vec<&str> {
+    let mut v = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                v.push(args[start..i].trim());
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    v.push(args[start..].trim());
+    v
+}
+
+fn from_hex(s: &str) -> Option<String> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return None;
+    }
+    let v = (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok()).collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(v).ok()
+}
+
+fn parse_udt_field(field: &str) -> Option<(String, Opt)> {
+    let i = field.find(':')?;
+    Some((from_hex(&field[..i])?, parse_class_name(&field[i + 1..])?))
+}
+
+fn class_name_depth(class_name: &str) -> u32 {
+    let mut depth = 0u32;
+    let mut max_depth = 0;
+    for c in class_name.chars() {
+        match c {
+            '(' => {
+                depth += 1;
+                max_depth = max_depth.max(depth);
+            },
+            ')' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+    }
+    max_depth
+}
+
+pub fn parse_class_name(class_name: &str) -> Option<Opt> {
+    let class_name = class_name.trim();
+    if class_name_depth(class_name) > DEFAULT_MAX_NESTING_DEPTH {
+        return None;
+    }
+    let name = class_name.trim_start_matches(MARSHAL_PACKAGE);
+    let (name, args) = match name.find('(') {
+        Some(i) if name.ends_with(')') => (&name[..i], split_class_args(&name[i + 1..name.len() - 1])),
+        Some(_) => return None,
+        None => {
+            if let Some((_, id)) = MARSHAL_CLASSES.iter().find(|(e, _)| *e == name) {
+                return Some(Opt::new(*id));
+            }
+            return if is_class_name(class_name) { Some(Opt::custom(class_name)) } else { None };
+        },
+    };
+
+    let (id, value) = match (name, args.as_slice()) {
+        ("FrozenType", [ty]) | ("ReversedType", [ty]) => return parse_class_name(ty),
+        ("ListType", [ty]) => (OptIds::List, OptValue::List(Box::new(parse_class_name(ty)?))),
+        ("SetType", [ty]) => (OptIds::Set, OptValue::Set(Box::new(parse_class_name(ty)?))),
+        ("MapType", [k, v]) => {
+            (OptIds::Map, OptValue::Map(Box::new(parse_class_name(k)?), Box::new(parse_class_name(v)?)))
+        },
+        ("TupleType", types) => {
+            (OptIds::Tuple, OptValue::Tuple(types.iter().map(|ty| parse_class_name(ty)).collect::<Option<_>>()?))
+        },
+        ("UserType", [ks, name, fields @ ..]) => {
+            let fields = fields.iter().map(|field| parse_udt_field(field)).collect::<Option<_>>()?;
+            return Some(udt_opt(ks.to_string(), from_hex(name)?, fields));
+        },
+        ("VectorType", _) => return parse_vector_type(class_name).map(|_| Opt::custom(class_name)),
+        _ => return None,
+    };
+    Some(Opt {
+        id,
+        value,
+    })
+}
+
+pub fn parse_vector_type(class_name: &str) -> Option<(Opt, usize)> {
+    let class_name = class_name.trim().trim_start_matches(MARSHAL_PACKAGE);
+    if !class_name.starts_with("VectorType(") || !class_name.ends_with(')') {
+        return None;
+    }
+
+    let args = &class_name["VectorType(".len()..class_name.len() - 1];
+    let i = args.rfind(',')?;
+    let dimension = args[i + 1..].trim().parse().ok()?;
+    Some((parse_class_name(&args[..i])?, dimension))
+}
+
+struct TypeParser<'a> {
+    s: &'a str,
+    pos: usize,
+    depth: u32,
+}
+
+impl<'a> TypeParser<'a> {
+    fn new(s: &'a str) -> TypeParser<'a> {
+        TypeParser {
+            s,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    fn parse(mut self) -> ProtResult<Opt> {
+        let ty = self.parse_type()?;
+        match self.peek() {
+            Some(_) => Err(self.err()),
+            None => Ok(ty),
+        }
+    }
+
+    fn err(&self) -> ProtError {
+        ProtError::InvalidFormat("Opt", self.s.to_string())
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.s[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+        self.s[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, c: char) -> ProtResult<()> {
+        if self.eat(c) { Ok(()) } else { Err(self.err()) }
+    }
+
+    fn quoted(&mut self, quote: char) -> ProtResult<String> {
+        let mut v = String::new();
+        let mut chars = self.s[self.pos..].char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c != quote {
+                v.push(c);
+                continue;
+            }
+            if let Some(&(_, next)) = chars.peek() {
+                if next == quote {
+                    chars.next();
+                    v.push(quote);
+                    continue;
+                }
+            }
+            self.pos += i + 1;
+            return Ok(v);
+        }
+        Err(self.err())
+    }
+
+    fn ident(&mut self) -> ProtResult<(String, bool)> {
+        if self.eat('"') {
+            return Ok((self.quoted('"')?, true));
+        }
+
+        let rest = &self.s[self.pos..];
+        let len = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.err());
+        }
+        self.pos += len;
+        Ok((rest[..len].to_ascii_lowercase(), false))
+    }
+
+    fn dimension(&mut self) -> ProtResult<usize> {
+        self.peek();
+        let rest = &self.s[self.pos..];
+        let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        self.pos += len;
+        rest[..len].parse().map_err(|_| self.err())
+    }
+
+    fn types(&mut self, n: usize) -> ProtResult<Vec<Opt>> {
+        self.expect('<')?;
+        let mut v = vec![self.parse_type()?];
+        while self.eat(',') {
+            v.push(self.parse_type()?);
+        }
+        self.expect('>')?;
+
+        if n > 0 && v.len() != n {
+            return Err(self.err());
+        }
+        Ok(v)
+    }
+
+    fn parse_type(&mut self) -> ProtResult<Opt> {
+        if self.depth > DEFAULT_MAX_NESTING_DEPTH {
+            return Err(self.err());
+        }
+
+        self.depth += 1;
+        let ty = self.parse_nested_type();
+        self.depth -= 1;
+        ty
+    }
+
+    fn parse_nested_type(&mut self) -> ProtResult<Opt> {
+        if self.eat('\'') {
+            return Ok(Opt::custom(&self.quoted('\'')?));
+        }
+
+        let (name, quoted) = self.ident()?;
+        if self.eat('.') {
+            return Ok(udt_opt(name, self.ident()?.0, Vec::new()));
+        }
+        if quoted {
+            return Ok(udt_opt(String::new(), name, Vec::new()));
+        }
+
+        let (id, value) = match name.as_str() {
+            "frozen" => return Ok(self.types(1)?.remove(0)),
+            "list" => (OptIds::List, OptValue::List(Box::new(self.types(1)?.remove(0)))),
+            "set" => (OptIds::Set, OptValue::Set(Box::new(self.types(1)?.remove(0)))),
+            "map" => {
+                let mut v = self.types(2)?;
+                let v = (Box::new(v.remove(0)), Box::new(v.remove(0)));
+                (OptIds::Map, OptValue::Map(v.0, v.1))
+            },
+            "tuple" => (OptIds::Tuple, OptValue::Tuple(self.types(0)?)),
+            "vector" => {
+                self.expect('<')?;
+                let ty = self.parse_type()?;
+                self.expect(',')?;
+                let dimension = self.dimension()?;
+                self.expect('>')?;
+                return Opt::vector(&ty, dimension).ok_or_else(|| self.err());
+            },
+            _ => match CQL_TYPES.iter().find(|(e, _)| *e == name) {
+                Some((_, id)) => return Ok(Opt::new(*id)),
+                None => return Ok(udt_opt(String::new(), name, Vec::new())),
+            },
+        };
+        Ok(Opt {
+            id,
+            value,
+        })
+    }
+}
+
+fn fmt_ident(f: &mut Formatter, ident: &str) -> fmt::Result {
+    let plain = ident.starts_with(|c: char| c.is_ascii_lowercase())
+        && ident.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if plain {
+        write!(f, "{}", ident)
+    } else {
+        write!(f, "\"{}\"", ident.replace('"', "\"\""))
+    }
+}
+
+fn fmt_cql_type(ty: &Opt, f: &mut Formatter, nested: bool) -> fmt::Result {
+    let frozen = match ty.value {
+        OptValue::List(_) | OptValue::Map(..) | OptValue::Set(_) | OptValue::Udt(_) => nested,
+        _ => false,
+    };
+    if frozen {
+        write!(f, "frozen<")?;
+        fmt_cql_type(ty, f, false)?;
+        return write!(f, ">");
+    }
+
+    match ty.value {
+        OptValue::None => match CQL_TYPES.iter().find(|(_, id)| *id == ty.id) {
+            Some((name, _)) => write!(f, "{}", name),
+            None => f.write_str(&format!("{:?}", ty.id).to_ascii_lowercase()),
+        },
+        OptValue::Custom(ref class_name) => match ty.vector_type() {
+            Some((ty, dimension)) => {
+                write!(f, "vector<")?;
+                fmt_cql_type(&ty, f, true)?;
+                write!(f, ", {}>", dimension)
+            },
+            None => write!(f, "'{}'", class_name.replace('\'', "''")),
+        },
+        OptValue::List(ref ty) => {
+            write!(f, "list<")?;
+            fmt_cql_type(ty, f, true)?;
+            write!(f, ">")
+        },
+        OptValue::Map(ref k, ref v) => {
+            write!(f, "map<")?;
+            fmt_cql_type(k, f, true)?;
+            write!(f, ", ")?;
+            fmt_cql_type(v, f, true)?;
+            write!(f, ">")
+        },
+        OptValue::Set(ref ty) => {
+            write!(f, "set<")?;
+            fmt_cql_type(ty, f, true)?;
+            write!(f, ">")
+        },
+        OptValue::Udt(ref udt) => {
+            if !udt.ks.is_empty() {
+                fmt_ident(f, &udt.ks)?;
+                write!(f, ".")?;
+            }
+            fmt_ident(f, &udt.name)
+        },
+        OptValue::Tuple(ref types) => {
+            write!(f, "tuple<")?;
+            for (i, ty) in types.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                fmt_cql_type(ty, f, true)?;
+            }
+            write!(f, ">")
+        },
+    }
+}
+
+impl Display for Opt {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        fmt_cql_type(self, f, false)
+    }
+}
+
+impl FromStr for Opt {
+    type Err = ProtError;
+
+    fn from_str(s: &str) -> ProtResult<Opt> {
+        if s.trim().starts_with(MARSHAL_PACKAGE) {
+            return parse_class_name(s).ok_or_else(|| ProtError::InvalidFormat("Opt", s.to_string()));
+        }
+        TypeParser::new(s).parse()
+    }
+}
+
+fn fixed_length(id: OptIds) -> Option<usize> {
+    match id {
+        OptIds::Boolean | OptIds::Tinyint => Some(1),
+        OptIds::Smallint => Some(2),
+        OptIds::Int | OptIds::Float | OptIds::Date => Some(4),
+        OptIds::Bigint | OptIds::Counter | OptIds::Double | OptIds::Timestamp | OptIds::Time => Some(8),
+        OptIds::Uuid | OptIds::Timeuuid => Some(16),
+        _ => None,
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -143,6 +599,7 @@ impl Default for Consistency {
 
 pub type StringMap = HashMap<String, String>;
 pub type StringMultimap = HashMap<String, StringList>;
+pub type BytesMap = HashMap<String, Bytes>;
 
 pub trait Type {
     fn length(&self) -> u32;
@@ -283,6 +740,12 @@ impl Type for StringMultimap {
     }
 }
 
+impl Type for BytesMap {
+    fn length(&self) -> u32 {
+        self.iter().fold(len::SHORT, |len, (k, v)| len + k.length() + v.length())
+    }
+}
+
 #[derive(Debug, Eq, Hash, PartialEq)]
 pub struct Duration {
     pub months: i32,
@@ -306,6 +769,64 @@ impl Display for Duration {
     }
 }
 
+#[derive(Default, Eq, Hash, PartialEq)]
+pub struct Udt {
+    pub ks: String,
+    pub name: String,
+    pub fields: Vec<(String, DataTypes)>,
+}
+
+impl Udt {
+    pub fn new(ks: &str, name: &str, fields: Vec<(String, DataTypes)>) -> Udt {
+        Udt {
+            ks: ks.to_string(),
+            name: name.to_string(),
+            fields,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DataTypes> {
+        self.fields.iter().find(|(field, _)| field == name).map(|(_, v)| v)
+    }
+}
+
+impl Debug for Udt {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if !self.name.is_empty() {
+            write!(f, "{}.{} ", self.ks, self.name)?;
+        }
+        write!(f, "{{")?;
+        for (i, (name, v)) in self.fields.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {:?}", name, v)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[derive(Eq, Hash, PartialEq)]
+pub struct Custom {
+    pub class_name: String,
+    pub value: Box<DataTypes>,
+}
+
+impl Custom {
+    pub fn new(class_name: &str, value: DataTypes) -> Custom {
+        Custom {
+            class_name: class_name.to_string(),
+            value: Box::new(value),
+        }
+    }
+}
+
+impl Debug for Custom {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Debug::fmt(&self.value, f)
+    }
+}
+
 #[derive(PartialEq)]
 pub enum DataTypes {
     Null,
@@ -330,10 +851,12 @@ pub enum DataTypes {
     Tinyint(i8),
     Duration(Duration),
     List(Vec<DataTypes>),
-    Map(HashMap<DataTypes, DataTypes>),
-    Set(HashSet<DataTypes>),
-    Udt(Vec<DataTypes>),
+    Map(Vec<(DataTypes, DataTypes)>),
+    Set(Vec<DataTypes>),
+    Udt(Udt),
     Tuple(Vec<DataTypes>),
+    Custom(Custom),
+    Vector(Vec<DataTypes>),
 }
 
 impl Hash for DataTypes {
@@ -348,14 +871,6 @@ impl Hash for DataTypes {
                 let v = marshal_double(v).unwrap().unwrap();
                 Hash::hash(&v, state);
             },
-            Self::Map(ref v) => {
-                let v = marshal_map(v).unwrap().unwrap();
-                Hash::hash(&v, state);
-            },
-            Self::Set(ref v) => {
-                let v = marshal_set(v).unwrap().unwrap();
-                Hash::hash(&v, state);
-            },
             Self::Ascii(ref v) => Hash::hash(v, state),
             Self::Bigint(ref v) => Hash::hash(v, state),
             Self::Blob(ref v) => Hash::hash(v, state),
@@ -375,8 +890,12 @@ impl Hash for DataTypes {
             Self::Tinyint(ref v) => Hash::hash(v, state),
             Self::Duration(ref v) => Hash::hash(v, state),
             Self::List(ref v) => Hash::hash(v, state),
+            Self::Map(ref v) => Hash::hash(v, state),
+            Self::Set(ref v) => Hash::hash(v, state),
             Self::Udt(ref v) => Hash::hash(v, state),
             Self::Tuple(ref v) => Hash::hash(v, state),
+            Self::Custom(ref v) => Hash::hash(v, state),
+            Self::Vector(ref v) => Hash::hash(v, state),
         }
     }
 }
@@ -408,10 +927,12 @@ impl Debug for DataTypes {
             Self::Tinyint(ref v) => write!(f, "{}", v),
             Self::Duration(ref v) => write!(f, "{}", v),
             Self::List(ref v) => write!(f, "{:?}", v),
-            Self::Map(ref v) => write!(f, "{:?}", v),
-            Self::Set(ref v) => write!(f, "{:?}", v),
+            Self::Map(ref v) => f.debug_map().entries(v.iter().map(|(k, v)| (k, v))).finish(),
+            Self::Set(ref v) => f.debug_set().entries(v.iter()).finish(),
             Self::Udt(ref v) => write!(f, "{:?}", v),
             Self::Tuple(ref v) => write!(f, "{:?}", v),
+            Self::Custom(ref v) => write!(f, "{:?}", v),
+            Self::Vector(ref v) => write!(f, "{:?}", v),
         }
     }
 }
@@ -419,10 +940,10 @@ impl Debug for DataTypes {
 macro_rules! match_marshal {
     ($v:expr, $($data_type:ident, $ty:tt),+) => (
         match $v {
+            DataTypes::Null => Ok(None),
             $(DataTypes::$data_type(ref v) => {
                 concat_idents!(marshal_, $ty)(v)
             })*
-            _ => unreachable!("{:?}", $v),
         }
     );
 }
@@ -448,7 +969,14 @@ pub fn marshal(v: &DataTypes) -> ProtResult<Bytes> {
                    Time, time,
                    Smallint, smallint,
                    Tinyint, tinyint,
-                   Duration, duration)
+                   Duration, duration,
+                   List, list,
+                   Map, map,
+                   Set, set,
+                   Udt, udt,
+                   Tuple, tuple,
+                   Custom, custom,
+                   Vector, vector)
 }
 
 pub fn unmarshal(ty: &Opt, bytes: &Vec<u8>) -> ProtResult<DataTypes> {
@@ -465,7 +993,7 @@ macro_rules! match_unmarshal_simple {
                 let v = concat_idents!(unmarshal_, $ty)($bytes)?;
                 Ok(DataTypes::$opt_id(v))
             })*
-            _ => unreachable!("{:?}", $id),
+            _ => Err(ProtError::InvalidEnumValue("OptIds", format!("{:?}", $id))),
         }
     );
 }
@@ -501,7 +1029,7 @@ pub fn unmarshal_complex(ty: &Opt, bytes: &Vec<u8>) -> ProtResult<DataTypes> {
                 let v = unmarshal_list(ty, bytes)?;
                 Ok(DataTypes::List(v))
             } else {
-                unreachable!("{:?}", ty.value)
+                Err(ProtError::InvalidEnumValue("OptValue", format!("{:?}", ty.value)))
             }
         },
         OptIds::Map => {
@@ -509,7 +1037,7 @@ pub fn unmarshal_complex(ty: &Opt, bytes: &Vec<u8>) -> ProtResult<DataTypes> {
                 let v = unmarshal_map(k, v, bytes)?;
                 Ok(DataTypes::Map(v))
             } else {
-                unreachable!("{:?}", ty.value)
+                Err(ProtError::InvalidEnumValue("OptValue", format!("{:?}", ty.value)))
             }
         },
         OptIds::Set => {
@@ -517,15 +1045,15 @@ pub fn unmarshal_complex(ty: &Opt, bytes: &Vec<u8>) -> ProtResult<DataTypes> {
                 let v = unmarshal_set(ty, bytes)?;
                 Ok(DataTypes::Set(v))
             } else {
-                unreachable!("{:?}", ty.value)
+                Err(ProtError::InvalidEnumValue("OptValue", format!("{:?}", ty.value)))
             }
         },
         OptIds::Udt => {
             if let OptValue::Udt(ref ty) = ty.value {
-                let v = unmarshal_udt(&ty.fields, bytes)?;
+                let v = unmarshal_udt(ty, bytes)?;
                 Ok(DataTypes::Udt(v))
             } else {
-                unreachable!("{:?}", ty.value)
+                Err(ProtError::InvalidEnumValue("OptValue", format!("{:?}", ty.value)))
             }
         },
         OptIds::Tuple => {
@@ -533,10 +1061,21 @@ pub fn unmarshal_complex(ty: &Opt, bytes: &Vec<u8>) -> ProtResult<DataTypes> {
                 let v = unmarshal_tuple(types, bytes)?;
                 Ok(DataTypes::Tuple(v))
             } else {
-                unreachable!("{:?}", ty.value)
+                Err(ProtError::InvalidEnumValue("OptValue", format!("{:?}", ty.value)))
             }
         },
-        _ => unreachable!("{:?}", ty.id),
+        OptIds::Custom => {
+            if let Some((ty, dimension)) = ty.vector_type() {
+                let v = unmarshal_vector(&ty, dimension, bytes)?;
+                Ok(DataTypes::Vector(v))
+            } else if let OptValue::Custom(ref class_name) = ty.value {
+                let v = unmarshal_custom(class_name, bytes)?;
+                Ok(DataTypes::Custom(v))
+            } else {
+                Err(ProtError::InvalidEnumValue("OptValue", format!("{:?}", ty.value)))
+            }
+        },
+        _ => Err(ProtError::InvalidEnumValue("OptIds", format!("{:?}", ty.id))),
     }
 }
 
@@ -644,7 +1183,7 @@ pub fn marshal_timestamp(v: &DateTime<Utc>) -> ProtResult<Bytes> {
 
 pub fn unmarshal_timestamp(bytes: &Vec<u8>) -> ProtResult<DateTime<Utc>> {
     let v = bytes.as_slice().read_i64::<BigEndian>()?;
-    Ok(Utc.timestamp_millis(v))
+    Utc.timestamp_millis_opt(v).single().ok_or_else(|| ProtError::InvalidFormat("DateTime<Utc>", v.to_string()))
 }
 
 pub fn marshal_uuid(v: &Uuid) -> ProtResult<Bytes> {
@@ -702,7 +1241,7 @@ pub fn unmarshal_inet(bytes: &Vec<u8>) -> ProtResult<IpAddr> {
             bytes.as_slice().read_exact(&mut octets)?;
             IpAddr::V6(Ipv6Addr::from(octets))
         },
-        _ => unreachable!(bytes.len()),
+        _ => return Err(ProtError::InvalidLength(bytes.len() as i64)),
     };
     Ok(v)
 }
@@ -710,13 +1249,15 @@ pub fn unmarshal_inet(bytes: &Vec<u8>) -> ProtResult<IpAddr> {
 pub fn marshal_date(v: &Date<Utc>) -> ProtResult<Bytes> {
     let mut bytes = Vec::with_capacity(4);
     let days = v.signed_duration_since(Utc.ymd(1970, 1, 1)).num_days();
-    bytes.write_u32::<BigEndian>(days as u32 + std::i32::MIN as u32)?;
+    bytes.write_u32::<BigEndian>((days as u32).wrapping_add(i32::MIN as u32))?;
     Ok(Some(bytes))
 }
 
 pub fn unmarshal_date(bytes: &Vec<u8>) -> ProtResult<Date<Utc>> {
-    let days = bytes.as_slice().read_u32::<BigEndian>()? - std::i32::MIN as u32;
-    Ok(Utc.ymd(1970, 1, 1).add(StdDuration::days(days as i64)))
+    let v = bytes.as_slice().read_u32::<BigEndian>()?;
+    let days = v.wrapping_sub(i32::MIN as u32) as i32;
+    Utc.ymd(1970, 1, 1).checked_add_signed(StdDuration::days(i64::from(days)))
+        .ok_or_else(|| ProtError::InvalidFormat("Date<Utc>", v.to_string()))
 }
 
 pub fn marshal_time(v: &NaiveTime) -> ProtResult<Bytes> {
@@ -728,9 +1269,16 @@ pub fn marshal_time(v: &NaiveTime) -> ProtResult<Bytes> {
 
 pub fn unmarshal_time(bytes: &Vec<u8>) -> ProtResult<NaiveTime> {
     let nanoseconds = bytes.as_slice().read_i64::<BigEndian>()?;
+    if nanoseconds < 0 {
+        return Err(ProtError::InvalidFormat("NaiveTime", nanoseconds.to_string()));
+    }
     let nano = nanoseconds % 1_000_000_000;
     let seconds = (nanoseconds - nano) / 1_000_000_000;
-    Ok(NaiveTime::from_num_seconds_from_midnight(seconds as u32, nano as u32))
+    if seconds >= 86_400 {
+        return Err(ProtError::InvalidFormat("NaiveTime", nanoseconds.to_string()));
+    }
+    NaiveTime::from_num_seconds_from_midnight_opt(seconds as u32, nano as u32)
+        .ok_or_else(|| ProtError::InvalidFormat("NaiveTime", nanoseconds.to_string()))
 }
 
 pub fn marshal_smallint(v: &i16) -> ProtResult<Bytes> {
@@ -785,6 +1333,7 @@ pub fn marshal_list(v: &Vec<DataTypes>) -> ProtResult<Bytes> {
 pub fn unmarshal_list(ty: &Opt, bytes: &Vec<u8>) -> ProtResult<Vec<DataTypes>> {
     let mut decoder = Decoder::new(Cursor::new(bytes));
     let len = decoder.read_int()?;
+    decoder.check_count(i64::from(len))?;
 
     let mut v = Vec::new();
     for _ in 1..=len {
@@ -798,7 +1347,7 @@ pub fn unmarshal_list(ty: &Opt, bytes: &Vec<u8>) -> ProtResult<Vec<DataTypes>> {
     Ok(v)
 }
 
-pub fn marshal_map(v: &HashMap<DataTypes, DataTypes>) -> ProtResult<Bytes> {
+pub fn marshal_map(v: &Vec<(DataTypes, DataTypes)>) -> ProtResult<Bytes> {
     let mut encoder = Encoder::new(Cursor::new(Vec::new()));
     encoder.write_int(v.len() as Int)?;
     for (k, v) in v {
@@ -808,11 +1357,12 @@ pub fn marshal_map(v: &HashMap<DataTypes, DataTypes>) -> ProtResult<Bytes> {
     Ok(Some(encoder.into_io().into_inner()))
 }
 
-pub fn unmarshal_map(key_type: &Opt, value_type: &Opt, bytes: &Vec<u8>) -> ProtResult<HashMap<DataTypes, DataTypes>> {
+pub fn unmarshal_map(key_type: &Opt, value_type: &Opt, bytes: &Vec<u8>) -> ProtResult<Vec<(DataTypes, DataTypes)>> {
     let mut decoder = Decoder::new(Cursor::new(bytes));
     let len = decoder.read_int()?;
+    decoder.check_count(i64::from(len))?;
 
-    let mut map = HashMap::new();
+    let mut map = Vec::with_capacity(len as usize);
     for _ in 1..=len {
         let bytes = decoder.read_bytes()?;
         let k = match bytes {
@@ -826,12 +1376,12 @@ pub fn unmarshal_map(key_type: &Opt, value_type: &Opt, bytes: &Vec<u8>) -> ProtR
             None => DataTypes::Null,
         };
 
-        map.insert(k, v);
+        map.push((k, v));
     }
     Ok(map)
 }
 
-pub fn marshal_set(v: &HashSet<DataTypes>) -> ProtResult<Bytes> {
+pub fn marshal_set(v: &Vec<DataTypes>) -> ProtResult<Bytes> {
     let mut encoder = Encoder::new(Cursor::new(Vec::new()));
     encoder.write_int(v.len() as Int)?;
     for e in v {
@@ -840,42 +1390,62 @@ pub fn marshal_set(v: &HashSet<DataTypes>) -> ProtResult<Bytes> {
     Ok(Some(encoder.into_io().into_inner()))
 }
 
-pub fn unmarshal_set(ty: &Opt, bytes: &Vec<u8>) -> ProtResult<HashSet<DataTypes>> {
+pub fn unmarshal_set(ty: &Opt, bytes: &Vec<u8>) -> ProtResult<Vec<DataTypes>> {
     let mut decoder = Decoder::new(Cursor::new(bytes));
     let len = decoder.read_int()?;
+    decoder.check_count(i64::from(len))?;
 
-    let mut v = HashSet::new();
+    let mut v = Vec::with_capacity(len as usize);
     for _ in 1..=len {
         let bytes = decoder.read_bytes()?;
         let e = match bytes {
             Some(ref bytes) => unmarshal(ty, bytes)?,
             None => DataTypes::Null,
         };
-        v.insert(e);
+        v.push(e);
     }
     Ok(v)
 }
 
-pub fn marshal_udt(v: &Vec<DataTypes>) -> ProtResult<Bytes> {
+pub fn marshal_udt(v: &Udt) -> ProtResult<Bytes> {
     let mut encoder = Encoder::new(Cursor::new(Vec::new()));
-    for e in v {
+    for (_, e) in v.fields.iter() {
         encoder.write_bytes(&marshal(&e)?)?;
     }
     Ok(Some(encoder.into_io().into_inner()))
 }
 
-pub fn unmarshal_udt(types: &Vec<(String, Opt)>, bytes: &Vec<u8>) -> ProtResult<Vec<DataTypes>> {
+pub fn marshal_udt_as(v: &Udt, ty: &OptUdt) -> ProtResult<Bytes> {
+    if let Some((name, _)) = v.fields.iter().find(|(name, _)| ty.fields.iter().all(|(field, _)| field != name)) {
+        return Err(ProtError::UnknownField(name.to_string()));
+    }
+
+    let mut encoder = Encoder::new(Cursor::new(Vec::new()));
+    for (name, _) in ty.fields.iter() {
+        let bytes = match v.get(name) {
+            Some(e) => marshal(e)?,
+            None => None,
+        };
+        encoder.write_bytes(&bytes)?;
+    }
+    Ok(Some(encoder.into_io().into_inner()))
+}
+
+pub fn unmarshal_udt(ty: &OptUdt, bytes: &Vec<u8>) -> ProtResult<Udt> {
     let mut decoder = Decoder::new(Cursor::new(bytes));
-    let mut v = Vec::new();
-    for ty in types {
-        let bytes = decoder.read_bytes()?;
-        let e = match bytes {
-            Some(ref bytes) => unmarshal(&ty.1, bytes)?,
-            None => DataTypes::Null,
+    let mut fields = Vec::with_capacity(ty.fields.len());
+    for (name, ty) in ty.fields.iter() {
+        let e = if decoder.io().position() < bytes.len() as u64 {
+            match decoder.read_bytes()? {
+                Some(ref bytes) => unmarshal(ty, bytes)?,
+                None => DataTypes::Null,
+            }
+        } else {
+            DataTypes::Null
         };
-        v.push(e);
+        fields.push((name.to_string(), e));
     }
-    Ok(v)
+    Ok(Udt::new(&ty.ks, &ty.name, fields))
 }
 
 pub fn marshal_tuple(v: &Vec<DataTypes>) -> ProtResult<Bytes> {
@@ -900,3 +1470,430 @@ pub fn unmarshal_tuple(types: &Vec<Opt>, bytes: &Vec<u8>) -> ProtResult<Vec<Data
     Ok(v)
 }
 
+pub fn marshal_custom(v: &Custom) -> ProtResult<Bytes> {
+    if let Some(codec) = registry::codec(&v.class_name) {
+        return codec.marshal(&v.class_name, &v.value);
+    }
+
+    match *v.value {
+        DataTypes::Blob(ref bytes) => Ok(Some(bytes.clone())),
+        _ => Err(ProtError::TypeMismatch("Custom", v.class_name.clone())),
+    }
+}
+
+fn is_fixed_length(v: &DataTypes) -> bool {
+    matches!(v,
+             DataTypes::Boolean(_) | DataTypes::Tinyint(_) | DataTypes::Smallint(_) | DataTypes::Int(_)
+             | DataTypes::Float(_) | DataTypes::Date(_) | DataTypes::Bigint(_) | DataTypes::Counter(_)
+             | DataTypes::Double(_) | DataTypes::Timestamp(_) | DataTypes::Time(_)
+             | DataTypes::Uuid(_) | DataTypes::Timeuuid(_))
+}
+
+fn write_vector_element(v: &mut Vec<u8>, bytes: Bytes, fixed: bool) -> ProtResult<()> {
+    let bytes = bytes.ok_or(ProtError::UnexpectedNull("Vector"))?;
+    if !fixed {
+        v.extend_from_slice(&vint::encode_u64_vec(bytes.len() as u64));
+    }
+    v.extend_from_slice(&bytes);
+    Ok(())
+}
+
+fn split_vector<'a>(ty: &Opt, dimension: usize, bytes: &'a [u8]) -> ProtResult<Vec<&'a [u8]>> {
+    let fixed = fixed_length(ty.id);
+    let mut v = Vec::with_capacity(dimension);
+    let mut pos = 0;
+    for _ in 0..dimension {
+        let len = match fixed {
+            Some(len) => len,
+            None => {
+                let first = *bytes.get(pos).ok_or(ProtError::InvalidLength(pos as i64))?;
+                if pos + (!first).leading_zeros() as usize >= bytes.len() {
+                    return Err(ProtError::InvalidLength(bytes.len() as i64));
+                }
+                let (len, n) = vint::decode_u64(&bytes[pos..]);
+                pos += n;
+                usize::try_from(len).map_err(|_| ProtError::InvalidLength(len as i64))?
+            },
+        };
+        let end = match pos.checked_add(len) {
+            Some(end) if end <= bytes.len() => end,
+            _ => return Err(ProtError::InvalidLength(len as i64)),
+        };
+        v.push(&bytes[pos..end]);
+        pos = end;
+    }
+
+    if pos != bytes.len() {
+        return Err(ProtError::InvalidLength(bytes.len() as i64));
+    }
+    Ok(v)
+}
+
+pub fn marshal_vector(v: &Vec<DataTypes>) -> ProtResult<Bytes> {
+    let mut bytes = Vec::new();
+    for e in v {
+        write_vector_element(&mut bytes, marshal(e)?, is_fixed_length(e))?;
+    }
+    Ok(Some(bytes))
+}
+
+pub fn unmarshal_vector(ty: &Opt, dimension: usize, bytes: &[u8]) -> ProtResult<Vec<DataTypes>> {
+    split_vector(ty, dimension, bytes)?.into_iter().map(|e| unmarshal(ty, &e.to_vec())).collect()
+}
+
+pub fn unmarshal_custom(class_name: &str, bytes: &[u8]) -> ProtResult<Custom> {
+    let v = match registry::codec(class_name) {
+        Some(codec) => codec.unmarshal(class_name, bytes)?,
+        None => DataTypes::Blob(bytes.to_vec()),
+    };
+    Ok(Custom::new(class_name, v))
+}
+
+#[cfg(feature = "derive")]
+pub use cql_derive::{FromUdt, IntoUdt};
+
+pub trait ToCql {
+    fn to_cql(&self, ty: &Opt) -> ProtResult<Value>;
+}
+
+pub trait FromCql: Sized {
+    fn from_cql(ty: &Opt, bytes: &Bytes) -> ProtResult<Self>;
+}
+
+pub(crate) fn type_mismatch<T: ?Sized>(ty: &Opt) -> ProtError {
+    ProtError::TypeMismatch(any::type_name::<T>(), format!("{:?}", ty.id))
+}
+
+fn check_type<T: ?Sized>(ty: &Opt, ids: &[OptIds]) -> ProtResult<()> {
+    if ids.contains(&ty.id) {
+        Ok(())
+    } else {
+        Err(type_mismatch::<T>(ty))
+    }
+}
+
+fn not_null<T: ?Sized>(bytes: &Bytes) -> ProtResult<&Vec<u8>> {
+    bytes.as_ref().ok_or_else(|| ProtError::UnexpectedNull(any::type_name::<T>()))
+}
+
+fn element_bytes<T: ToCql + ?Sized>(v: &T, ty: &Opt) -> ProtResult<Bytes> {
+    match v.to_cql(ty)? {
+        Value::Some(v) => Ok(Some(v)),
+        Value::None => Ok(None),
+        Value::NotSet => Err(type_mismatch::<T>(ty)),
+    }
+}
+
+fn element_type<T: ?Sized>(ty: &Opt) -> ProtResult<&Opt> {
+    match ty.value {
+        OptValue::List(ref t) | OptValue::Set(ref t) => Ok(t),
+        _ => Err(type_mismatch::<T>(ty)),
+    }
+}
+
+fn tuple_types<T: ?Sized>(ty: &Opt, len: usize) -> ProtResult<&Vec<Opt>> {
+    match ty.value {
+        OptValue::Tuple(ref types) if types.len() == len => Ok(types),
+        _ => Err(type_mismatch::<T>(ty)),
+    }
+}
+
+macro_rules! impl_cql {
+    ($T:ty, $marshal:ident, $unmarshal:ident, $($opt_id:ident),+) => (
+        impl ToCql for $T {
+            fn to_cql(&self, ty: &Opt) -> ProtResult<Value> {
+                check_type::<Self>(ty, &[$(OptIds::$opt_id),+])?;
+                Ok(Value::from($marshal(self)?))
+            }
+        }
+
+        impl FromCql for $T {
+            fn from_cql(ty: &Opt, bytes: &Bytes) -> ProtResult<Self> {
+                check_type::<Self>(ty, &[$(OptIds::$opt_id),+])?;
+                $unmarshal(not_null::<Self>(bytes)?)
+            }
+        }
+    );
+}
+
+impl_cql!(i8, marshal_tinyint, unmarshal_tinyint, Tinyint);
+impl_cql!(i16, marshal_smallint, unmarshal_smallint, Smallint);
+impl_cql!(i32, marshal_int, unmarshal_int, Int);
+impl_cql!(i64, marshal_bigint, unmarshal_bigint, Bigint, Counter);
+impl_cql!(f32, marshal_float, unmarshal_float, Float);
+impl_cql!(f64, marshal_double, unmarshal_double, Double);
+impl_cql!(bool, marshal_boolean, unmarshal_boolean, Boolean);
+impl_cql!(AsciiString, marshal_ascii, unmarshal_ascii, Ascii);
+impl_cql!(BigDecimal, marshal_decimal, unmarshal_decimal, Decimal);
+impl_cql!(BigInt, marshal_varint, unmarshal_varint, Varint);
+impl_cql!(DateTime<Utc>, marshal_timestamp, unmarshal_timestamp, Timestamp);
+impl_cql!(Date<Utc>, marshal_date, unmarshal_date, Date);
+impl_cql!(NaiveTime, marshal_time, unmarshal_time, Time);
+impl_cql!(Uuid, marshal_uuid, unmarshal_uuid, Uuid, Timeuuid);
+impl_cql!(IpAddr, marshal_inet, unmarshal_inet, Inet);
+impl_cql!(Duration, marshal_duration, unmarshal_duration, Duration);
+
+impl ToCql for str {
+    fn to_cql(&self, ty: &Opt) -> ProtResult<Value> {
+        check_type::<Self>(ty, &[OptIds::Varchar, OptIds::Ascii])?;
+        if ty.id == OptIds::Ascii && !self.is_ascii() {
+            return Err(type_mismatch::<Self>(ty));
+        }
+        Ok(Value::from(marshal_varchar(self)?))
+    }
+}
+
+impl ToCql for String {
+    fn to_cql(&self, ty: &Opt) -> ProtResult<Value> {
+        self.as_str().to_cql(ty)
+    }
+}
+
+impl FromCql for String {
+    fn from_cql(ty: &Opt, bytes: &Bytes) -> ProtResult<Self> {
+        check_type::<Self>(ty, &[OptIds::Varchar, OptIds::Ascii])?;
+        unmarshal_varchar(not_null::<Self>(bytes)?)
+    }
+}
+
+impl ToCql for Vec<u8> {
+    fn to_cql(&self, ty: &Opt) -> ProtResult<Value> {
+        check_type::<Self>(ty, &[OptIds::Blob])?;
+        Ok(Value::from(marshal_blob(self)?))
+    }
+}
+
+impl FromCql for Vec<u8> {
+    fn from_cql(ty: &Opt, bytes: &Bytes) -> ProtResult<Self> {
+        check_type::<Self>(ty, &[OptIds::Blob])?;
+        unmarshal_blob(not_null::<Self>(bytes)?)
+    }
+}
+
+impl<T: ToCql + ?Sized> ToCql for &T {
+    fn to_cql(&self, ty: &Opt) -> ProtResult<Value> {
+        (*self).to_cql(ty)
+    }
+}
+
+impl<T: ToCql> ToCql for Option<T> {
+    fn to_cql(&self, ty: &Opt) -> ProtResult<Value> {
+        match self {
+            Some(ref v) => v.to_cql(ty),
+            None => Ok(Value::None),
+        }
+    }
+}
+
+impl<T: FromCql> FromCql for Option<T> {
+    fn from_cql(ty: &Opt, bytes: &Bytes) -> ProtResult<Self> {
+        match bytes {
+            Some(_) => Ok(Some(T::from_cql(ty, bytes)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+fn collection_to_cql<'a, T: ToCql + 'a, I: ExactSizeIterator<Item = &'a T>>(v: I, ty: &Opt) -> ProtResult<Value> {
+    let mut encoder = Encoder::new(Cursor::new(Vec::new()));
+    encoder.write_int(v.len() as Int)?;
+    for e in v {
+        encoder.write_bytes(&element_bytes(e, ty)?)?;
+    }
+    Ok(Value::Some(encoder.into_io().into_inner()))
+}
+
+fn collection_from_cql<T: FromCql>(ty: &Opt, bytes: &Vec<u8>) -> ProtResult<Vec<T>> {
+    let mut decoder = Decoder::new(Cursor::new(bytes));
+    let len = decoder.read_int()?;
+    decoder.check_count(i64::from(len))?;
+
+    let mut v = Vec::with_capacity(len as usize);
+    for _ in 1..=len {
+        v.push(T::from_cql(ty, &decoder.read_bytes()?)?);
+    }
+    Ok(v)
+}
+
+impl<T: ToCql> ToCql for Vec<T> {
+    default fn to_cql(&self, ty: &Opt) -> ProtResult<Value> {
+        if let Some((ty, dimension)) = ty.vector_type() {
+            if self.len() != dimension {
+                return Err(ProtError::InvalidLength(self.len() as i64));
+            }
+
+            let fixed = fixed_length(ty.id).is_some();
+            let mut v = Vec::new();
+            for e in self {
+                write_vector_element(&mut v, element_bytes(e, &ty)?, fixed)?;
+            }
+            return Ok(Value::Some(v));
+        }
+
+        check_type::<Self>(ty, &[OptIds::List, OptIds::Set])?;
+        collection_to_cql(self.iter(), element_type::<Self>(ty)?)
+    }
+}
+
+impl<T: FromCql> FromCql for Vec<T> {
+    default fn from_cql(ty: &Opt, bytes: &Bytes) -> ProtResult<Self> {
+        if let Some((ty, dimension)) = ty.vector_type() {
+            let elements = split_vector(&ty, dimension, not_null::<Self>(bytes)?)?;
+            return elements.into_iter().map(|e| T::from_cql(&ty, &Some(e.to_vec()))).collect();
+        }
+
+        check_type::<Self>(ty, &[OptIds::List, OptIds::Set])?;
+        collection_from_cql(element_type::<Self>(ty)?, not_null::<Self>(bytes)?)
+    }
+}
+
+impl<T: ToCql + Eq + Hash> ToCql for HashSet<T> {
+    fn to_cql(&self, ty: &Opt) -> ProtResult<Value> {
+        check_type::<Self>(ty, &[OptIds::Set, OptIds::List])?;
+        let ty = element_type::<Self>(ty)?;
+        let mut elements = self.iter().map(|e| element_bytes(e, ty)).collect::<ProtResult<Vec<Bytes>>>()?;
+        elements.sort();
+
+        let mut encoder = Encoder::new(Cursor::new(Vec::new()));
+        encoder.write_int(elements.len() as Int)?;
+        for e in elements.iter() {
+            encoder.write_bytes(e)?;
+        }
+        Ok(Value::Some(encoder.into_io().into_inner()))
+    }
+}
+
+impl<T: FromCql + Eq + Hash> FromCql for HashSet<T> {
+    fn from_cql(ty: &Opt, bytes: &Bytes) -> ProtResult<Self> {
+        check_type::<Self>(ty, &[OptIds::Set, OptIds::List])?;
+        let v: Vec<T> = collection_from_cql(element_type::<Self>(ty)?, not_null::<Self>(bytes)?)?;
+        Ok(v.into_iter().collect())
+    }
+}
+
+impl<K: ToCql + Eq + Hash, V: ToCql> ToCql for HashMap<K, V> {
+    fn to_cql(&self, ty: &Opt) -> ProtResult<Value> {
+        let (key_type, value_type) = match ty.value {
+            OptValue::Map(ref k, ref v) => (k, v),
+            _ => return Err(type_mismatch::<Self>(ty)),
+        };
+
+        let mut entries = self.iter().map(|(k, v)| Ok((element_bytes(k, key_type)?, element_bytes(v, value_type)?)))
+            .collect::<ProtResult<Vec<(Bytes, Bytes)>>>()?;
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut encoder = Encoder::new(Cursor::new(Vec::new()));
+        encoder.write_int(entries.len() as Int)?;
+        for (k, v) in entries.iter() {
+            encoder.write_bytes(k)?;
+            encoder.write_bytes(v)?;
+        }
+        Ok(Value::Some(encoder.into_io().into_inner()))
+    }
+}
+
+impl<K: FromCql + Eq + Hash, V: FromCql> FromCql for HashMap<K, V> {
+    fn from_cql(ty: &Opt, bytes: &Bytes) -> ProtResult<Self> {
+        let (key_type, value_type) = match ty.value {
+            OptValue::Map(ref k, ref v) => (k, v),
+            _ => return Err(type_mismatch::<Self>(ty)),
+        };
+
+        let mut decoder = Decoder::new(Cursor::new(not_null::<Self>(bytes)?));
+        let len = decoder.read_int()?;
+        decoder.check_count(i64::from(len))?;
+
+        let mut map = HashMap::with_capacity(len as usize);
+        for _ in 1..=len {
+            let k = K::from_cql(key_type, &decoder.read_bytes()?)?;
+            let v = V::from_cql(value_type, &decoder.read_bytes()?)?;
+            map.insert(k, v);
+        }
+        Ok(map)
+    }
+}
+
+macro_rules! impl_cql_tuple {
+    ($len:expr, $($T:ident, $i:tt),+) => (
+        impl<$($T: ToCql),+> ToCql for ($($T,)+) {
+            fn to_cql(&self, ty: &Opt) -> ProtResult<Value> {
+                let types = tuple_types::<Self>(ty, $len)?;
+                let mut encoder = Encoder::new(Cursor::new(Vec::new()));
+                $(encoder.write_bytes(&element_bytes(&self.$i, &types[$i])?)?;)+
+                Ok(Value::Some(encoder.into_io().into_inner()))
+            }
+        }
+
+        impl<$($T: FromCql),+> FromCql for ($($T,)+) {
+            fn from_cql(ty: &Opt, bytes: &Bytes) -> ProtResult<Self> {
+                let types = tuple_types::<Self>(ty, $len)?;
+                let mut decoder = Decoder::new(Cursor::new(not_null::<Self>(bytes)?));
+                Ok(($($T::from_cql(&types[$i], &decoder.read_bytes()?)?,)+))
+            }
+        }
+    );
+}
+
+impl_cql_tuple!(1, A, 0);
+impl_cql_tuple!(2, A, 0, B, 1);
+impl_cql_tuple!(3, A, 0, B, 1, C, 2);
+impl_cql_tuple!(4, A, 0, B, 1, C, 2, D, 3);
+impl_cql_tuple!(5, A, 0, B, 1, C, 2, D, 3, E, 4);
+impl_cql_tuple!(6, A, 0, B, 1, C, 2, D, 3, E, 4, F, 5);
+
+fn invalid_field(name: &str) -> impl FnOnce(ProtError) -> ProtError + '_ {
+    move |e| ProtError::InvalidField(name.to_string(), Box::new(e))
+}
+
+fn udt_type<T: ?Sized>(ty: &Opt) -> ProtResult<&OptUdt> {
+    match ty.value {
+        OptValue::Udt(ref udt) => Ok(udt),
+        _ => Err(type_mismatch::<T>(ty)),
+    }
+}
+
+pub fn udt_to_cql<T: ?Sized>(ty: &Opt, values: &[(&str, &dyn ToCql)]) -> ProtResult<Value> {
+    let udt = udt_type::<T>(ty)?;
+    if let Some((name, _)) = values.iter().find(|(name, _)| udt.fields.iter().all(|(field, _)| field != name)) {
+        return Err(ProtError::UnknownField(name.to_string()));
+    }
+
+    let mut encoder = Encoder::new(Cursor::new(Vec::new()));
+    for (field, ty) in udt.fields.iter() {
+        let bytes = match values.iter().find(|(name, _)| name == field) {
+            Some((_, value)) => element_bytes(*value, ty).map_err(invalid_field(field))?,
+            None => None,
+        };
+        encoder.write_bytes(&bytes)?;
+    }
+    Ok(Value::Some(encoder.into_io().into_inner()))
+}
+
+pub struct UdtFields<'a> {
+    fields: Vec<(&'a str, &'a Opt, Bytes)>,
+}
+
+impl<'a> UdtFields<'a> {
+    pub fn decode<T: ?Sized>(ty: &'a Opt, bytes: &Bytes) -> ProtResult<UdtFields<'a>> {
+        let udt = udt_type::<T>(ty)?;
+        let bytes = not_null::<T>(bytes)?;
+        let len = bytes.len() as u64;
+        let mut decoder = Decoder::new(Cursor::new(bytes));
+
+        let mut fields = Vec::with_capacity(udt.fields.len());
+        for (name, ty) in udt.fields.iter() {
+            let v = if decoder.io().position() < len { decoder.read_bytes()? } else { None };
+            fields.push((name.as_str(), ty, v));
+        }
+        Ok(UdtFields {
+            fields,
+        })
+    }
+
+    pub fn get<T: FromCql>(&self, name: &str) -> ProtResult<T> {
+        match self.fields.iter().find(|(field, ..)| *field == name) {
+            Some((_, ty, bytes)) => T::from_cql(ty, bytes).map_err(invalid_field(name)),
+            None => Err(ProtError::MissingField(name.to_string())),
+        }
+    }
+}
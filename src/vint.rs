@@ -99,7 +99,7 @@ fn decode(bytes: &[u8]) -> (u64, usize) {
 }
 
 fn first_byte_mask(len: usize) -> u8 {
-    0xff >> len
+    0xffu8.checked_shr(len as u32).unwrap_or(0)
 }
 
 fn msb(len: usize) -> u8 {
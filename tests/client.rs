@@ -18,19 +18,12 @@ use cql::request::batch::BatchQuery;
 
 mod common;
 mod fake_snappy;
-mod snappy;
 
 fn new_compressor(compression: Compression, fake: bool) -> Box<dyn Compressor> {
-    match compression {
-        Compression::Snappy => {
-            if fake {
-                Box::new(fake_snappy::Snappy::new())
-            } else {
-                Box::new(snappy::Snappy::new())
-            }
-        },
-        Compression::Lz4 => unimplemented!("{:?}", compression),
+    if fake && compression == Compression::Snappy {
+        return Box::new(fake_snappy::Snappy::new());
     }
+    compression.compressor().unwrap_or_else(|| panic!("{} feature is required", compression))
 }
 
 #[derive(Default)]
@@ -106,7 +99,7 @@ impl Client {
         let options = Options::new();
         self.frame.encode(&options).unwrap();
 
-        let (_, rsp) = self.frame.decode().unwrap();
+        let rsp = self.frame.decode().unwrap().into_message();
         let supported = match rsp {
             MessageKind::Supported(m) => m,
             _ => unreachable!("{:?}", rsp),
@@ -125,7 +118,7 @@ impl Client {
         auth_response.set_token(auth_response_token("cassandra", "cassandra"));
         self.frame.encode(&auth_response).unwrap();
 
-        let (_, rsp) = self.frame.decode().unwrap();
+        let rsp = self.frame.decode().unwrap().into_message();
         let auth_success = match rsp {
             MessageKind::AuthSuccess(m) => m,
             _ => unreachable!("{:?}", rsp),
@@ -142,7 +135,7 @@ impl Client {
         register.set_events(&EventType::iter().collect::<Vec<EventType>>());
         self.frame.encode(&register).unwrap();
 
-        let (_, rsp) = self.frame.decode().unwrap();
+        let rsp = self.frame.decode().unwrap().into_message();
         let ready = match rsp {
             MessageKind::Ready(m) => m,
             _ => unreachable!("{:?}", rsp),
@@ -154,7 +147,9 @@ impl Client {
         self.register();
 
         loop {
-            let (stream_id, rsp) = self.frame.decode().unwrap();
+            let envelope = self.frame.decode().unwrap();
+            let stream_id = envelope.stream_id();
+            let rsp = envelope.into_message();
             let event = match rsp {
                 MessageKind::Event(m) => m,
                 _ => unreachable!("{:?}", rsp),
@@ -277,7 +272,7 @@ impl Client {
         self.auth();
 
         self.frame.encode(query).unwrap();
-        let (_, rsp) = self.frame.decode().unwrap();
+        let rsp = self.frame.decode().unwrap().into_message();
         let result = match rsp {
             MessageKind::Result(m) => m,
             _ => unreachable!("{:?}", rsp),
@@ -294,7 +289,7 @@ impl Client {
         self.auth();
 
         self.frame.encode(prepare).unwrap();
-        let (_, rsp) = self.frame.decode().unwrap();
+        let rsp = self.frame.decode().unwrap().into_message();
         let result = match rsp {
             MessageKind::Result(m) => m,
             _ => unreachable!("{:?}", rsp),
@@ -313,7 +308,7 @@ impl Client {
         self.auth();
 
         self.frame.encode(execute).unwrap();
-        let (_, rsp) = self.frame.decode().unwrap();
+        let rsp = self.frame.decode().unwrap().into_message();
         let result = match rsp {
             MessageKind::Result(m) => m,
             _ => unreachable!("{:?}", rsp),
@@ -326,7 +321,7 @@ impl Client {
         self.auth();
 
         self.frame.encode(batch).unwrap();
-        let (_, rsp) = self.frame.decode().unwrap();
+        let rsp = self.frame.decode().unwrap().into_message();
         let result = match rsp {
             MessageKind::Result(m) => m,
             _ => unreachable!("{:?}", rsp),
@@ -337,7 +332,7 @@ impl Client {
 
     fn startup(&mut self) {
         self.frame.encode(&self.startup).unwrap();
-        let (_, rsp) = self.frame.decode().unwrap();
+        let rsp = self.frame.decode().unwrap().into_message();
         let authenticate = match rsp {
             MessageKind::Authenticate(m) => m,
             _ => unreachable!("{:?}", rsp),
@@ -346,7 +341,7 @@ impl Client {
     }
 
     fn match_error(&mut self) -> Error {
-        let (_, rsp) = self.frame.decode().unwrap();
+        let rsp = self.frame.decode().unwrap().into_message();
         let error = match rsp {
             MessageKind::Error(m) => m,
             _ => unreachable!("{:?}", rsp),
@@ -759,7 +754,7 @@ fn prepare_insert() {
     let value = Value::Some(marshal_int(&123).unwrap().unwrap());
     params.set_values(vec![id, name, value]);
 
-    let e = Execute::from(prepared.id().to_vec(), params);
+    let e = Execute::from_prepared(&prepared, params);
     execute(&e);
 }
 
@@ -775,7 +770,7 @@ fn prepare_named_insert() {
     let value = Value::Some(marshal_int(&456).unwrap().unwrap());
     params.set_values(vec![value, id, name]);
 
-    let e = Execute::from(prepared.id().to_vec(), params);
+    let e = Execute::from_prepared(&prepared, params);
     execute(&e);
 }
 
@@ -788,7 +783,7 @@ fn prepare_select() {
     let id = Value::Some(marshal_varchar("a").unwrap().unwrap());
     params.set_values(vec![id]);
 
-    let e = Execute::from(prepared.id().to_vec(), params);
+    let e = Execute::from_prepared(&prepared, params);
     execute(&e);
 }
 
@@ -801,7 +796,7 @@ fn prepare_named_select() {
     let id = Value::Some(marshal_varchar("b").unwrap().unwrap());
     params.set_values(vec![id]);
 
-    let e = Execute::from(prepared.id().to_vec(), params);
+    let e = Execute::from_prepared(&prepared, params);
     execute(&e);
 }
 
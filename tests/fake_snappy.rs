@@ -1,6 +1,6 @@
 use cql::compression::Compressor;
 
-use snap::{Decoder, Encoder};
+use snap::{decompress_len, Decoder, Encoder};
 use std::io;
 
 pub struct Snappy {
@@ -18,14 +18,18 @@ impl Snappy {
 }
 
 impl Compressor for Snappy {
-    fn compress(&mut self, v: &[u8]) -> io::Result<Vec<u8>> {
-        let mut v = self.encoder.compress_vec(v)?;
-        v.append(&mut vec![0; 10]);
-        Ok(v)
+    fn compress(&mut self, src: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        dst.extend_from_slice(&self.encoder.compress_vec(src)?);
+        dst.extend_from_slice(&[0; 10]);
+        Ok(())
     }
 
-    fn decompress(&mut self, v: &[u8]) -> io::Result<Vec<u8>> {
-        let v = self.decoder.decompress_vec(v)?;
-        Ok(v)
+    fn decompress(&mut self, src: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        dst.extend_from_slice(&self.decoder.decompress_vec(src)?);
+        Ok(())
+    }
+
+    fn decompressed_length(&self, src: &[u8]) -> io::Result<usize> {
+        Ok(decompress_len(src)?)
     }
 }
@@ -1,4 +1,5 @@
 use cql::codec::*;
+use cql::def::Version;
 use cql::message::*;
 use cql::request::auth_response::AuthResponse;
 
@@ -26,10 +27,10 @@ fn eq() {
 #[test]
 fn length() {
     let mut m = AuthResponse::new();
-    assert_eq!(m.length(), 4);
+    assert_eq!(m.length(Version::default()), 4);
 
     m.set_token(crate::auth_response_token("cassandra", "cassandra"));
-    assert_eq!(m.length(), 24);
+    assert_eq!(m.length(Version::default()), 24);
 }
 
 #[test]
@@ -37,17 +38,17 @@ fn serde() {
     let mut codec = Codec::new(Cursor::new(Vec::new()));
 
     let mut a = AuthResponse::new();
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
     codec.io().set_position(0);
-    let b = AuthResponse::decode(&mut codec).unwrap();
+    let b = AuthResponse::decode(&mut codec, Version::default()).unwrap();
     assert_eq!(a, b);
 
     codec.io().set_position(0);
 
     a.set_token(crate::auth_response_token("cassandra", "cassandra"));
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
     println!("{:?}", codec.io().get_ref());
     codec.io().set_position(0);
-    let b = AuthResponse::decode(&mut codec).unwrap();
+    let b = AuthResponse::decode(&mut codec, Version::default()).unwrap();
     assert_eq!(a, b);
 }
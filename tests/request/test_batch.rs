@@ -2,8 +2,11 @@ use cql::codec::*;
 use cql::def::*;
 use cql::message::*;
 use cql::request::batch::*;
+use cql::result::ProtError;
 use cql::types::*;
 
+use strum::IntoEnumIterator;
+
 use std::io::Cursor;
 
 fn default() -> Batch {
@@ -31,23 +34,52 @@ fn eq() {
 #[test]
 fn length() {
     let m = default();
-    assert_eq!(m.length(), 9);
+    assert_eq!(m.length(Version::V4), 6);
+    assert_eq!(m.length(Version::V5), 9);
 
     let m = Batch::from(BatchType::Logged,
                         vec![BatchQuery::from(BatchQueryKind::Query(LongString::new("a")), Vec::new())]);
-    assert_eq!(m.length(), 17);
+    assert_eq!(m.length(Version::V4), 14);
+    assert_eq!(m.length(Version::V5), 17);
 }
 
 #[test]
 fn serde() {
-    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    let values = vec![Value::Some(marshal_int(&1).unwrap().unwrap()), Value::None];
+    let querys = vec![BatchQuery::from(BatchQueryKind::Query(LongString::new("a")), values),
+                      BatchQuery::new(BatchQueryKind::Execute(vec![0, 1]))];
+    let mut a = Batch::from(BatchType::Unlogged, querys);
+    a.set_serial_consistency(Consistency::LocalSerial);
+    a.set_timestamp(1);
 
-    let a = default();
-    a.encode(&mut codec).unwrap();
+    for version in Version::iter() {
+        let mut codec = Codec::new(Cursor::new(Vec::new()));
+        a.encode(&mut codec, version).unwrap();
+        assert_eq!(codec.io().position(), a.length(version) as u64);
+
+        codec.io().set_position(0);
+        let b = Batch::decode(&mut codec, version).unwrap();
+        println!("{}", b);
+        assert_eq!(a, b);
+    }
+}
+
+#[test]
+fn now_in_seconds() {
+    let mut a = Batch::from(BatchType::Logged, Vec::new());
+    a.set_now_in_seconds(1_600_000_000);
+    assert_eq!(a.length(Version::V4), 6);
+    assert_eq!(a.length(Version::V5), 13);
+
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    a.encode(&mut codec, Version::V5).unwrap();
     codec.io().set_position(0);
-    let b = Batch::decode(&mut codec).unwrap();
-    println!("{}", b);
+    let b = Batch::decode(&mut codec, Version::V5).unwrap();
     assert_eq!(a, b);
 
-    codec.io().set_position(0);
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    match a.encode(&mut codec, Version::V3) {
+        Err(ProtError::UnsupportedVersion("now_in_seconds", 3)) => {},
+        r => unreachable!("{:?}", r),
+    }
 }
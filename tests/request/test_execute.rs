@@ -1,8 +1,11 @@
 use cql::codec::*;
+use cql::def::Version;
 use cql::message::*;
 use cql::request::execute::Execute;
 use cql::request::query::QueryParams;
 
+use strum::IntoEnumIterator;
+
 use std::io::Cursor;
 
 fn default() -> Execute {
@@ -30,22 +33,44 @@ fn eq() {
 #[test]
 fn length() {
     let m = default();
-    assert_eq!(m.length(), 8);
+    assert_eq!(m.length(Version::V4), 5);
+    assert_eq!(m.length(Version::V5), 10);
+
+    let mut m = Execute::from(vec![0], QueryParams::default());
+    assert_eq!(m.length(Version::V4), 6);
+    assert_eq!(m.length(Version::V5), 11);
 
-    let m = Execute::from(vec![0], QueryParams::default());
-    assert_eq!(m.length(), 9);
+    m.set_result_metadata_id(vec![1, 2]);
+    assert_eq!(m.length(Version::V4), 6);
+    assert_eq!(m.length(Version::V5), 13);
 }
 
 #[test]
 fn serde() {
-    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    for version in Version::iter() {
+        let mut codec = Codec::new(Cursor::new(Vec::new()));
 
-    let a = default();
-    a.encode(&mut codec).unwrap();
-    codec.io().set_position(0);
-    let b = Execute::decode(&mut codec).unwrap();
-    println!("{}", b);
-    assert_eq!(a, b);
+        let mut params = QueryParams::default();
+        params.set_skip_metadata(true);
+        let mut a = Execute::from(vec![0], params);
+        if version >= Version::V5 {
+            a.set_result_metadata_id(vec![1, 2]);
+        }
+        a.encode(&mut codec, version).unwrap();
+        assert_eq!(codec.io().position(), a.length(version) as u64);
 
-    codec.io().set_position(0);
+        codec.io().set_position(0);
+        let b = Execute::decode(&mut codec, version).unwrap();
+        println!("{}", b);
+        assert_eq!(a, b);
+    }
+}
+
+#[test]
+fn result_metadata_id() {
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    let mut a = Execute::from(vec![0], QueryParams::default());
+    a.set_result_metadata_id(vec![1, 2]);
+    a.encode(&mut codec, Version::V5).unwrap();
+    assert_eq!(&codec.io().get_ref()[..7], &[0, 1, 0, 0, 2, 1, 2]);
 }
@@ -1,4 +1,5 @@
 use cql::codec::*;
+use cql::def::Version;
 use cql::message::*;
 use cql::request::options::Options;
 
@@ -15,9 +16,9 @@ fn serde() {
     let mut codec = Codec::new(Cursor::new(Vec::new()));
 
     let a = Options::new();
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
     assert_eq!(codec.io().get_ref().len(), 0);
 
-    let b = Options::decode(&mut codec).unwrap();
+    let b = Options::decode(&mut codec, Version::default()).unwrap();
     assert_eq!(a, b);
 }
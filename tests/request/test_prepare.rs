@@ -1,6 +1,10 @@
 use cql::codec::*;
+use cql::def::Version;
 use cql::message::*;
 use cql::request::prepare::Prepare;
+use cql::result::ProtError;
+
+use strum::IntoEnumIterator;
 
 use std::io::Cursor;
 
@@ -25,22 +29,44 @@ fn eq() {
 #[test]
 fn length() {
     let mut m = Prepare::from("");
-    assert_eq!(m.length(), 8);
+    assert_eq!(m.length(Version::V4), 4);
+    assert_eq!(m.length(Version::V5), 8);
 
     m.set_query("a");
-    assert_eq!(m.length(), 9);
+    assert_eq!(m.length(Version::V4), 5);
+    assert_eq!(m.length(Version::V5), 9);
+
+    m.set_keyspace("a");
+    assert_eq!(m.length(Version::V4), 5);
+    assert_eq!(m.length(Version::V5), 12);
 }
 
 #[test]
 fn serde() {
-    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    for version in Version::iter() {
+        let mut codec = Codec::new(Cursor::new(Vec::new()));
+
+        let a = Prepare::from("a");
+        a.encode(&mut codec, version).unwrap();
+        assert_eq!(codec.io().position(), a.length(version) as u64);
+
+        codec.io().set_position(0);
+        let b = Prepare::decode(&mut codec, version).unwrap();
+        println!("{}", b);
+        assert_eq!(a, b);
+    }
 
-    let a = Prepare::from("a");
-    a.encode(&mut codec).unwrap();
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    let mut a = Prepare::from("a");
+    a.set_keyspace("ks");
+    a.encode(&mut codec, Version::V5).unwrap();
     codec.io().set_position(0);
-    let b = Prepare::decode(&mut codec).unwrap();
-    println!("{}", b);
+    let b = Prepare::decode(&mut codec, Version::V5).unwrap();
     assert_eq!(a, b);
 
-    codec.io().set_position(0);
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    match a.encode(&mut codec, Version::V4) {
+        Err(ProtError::UnsupportedVersion("keyspace", 4)) => {},
+        r => unreachable!("{:?}", r),
+    }
 }
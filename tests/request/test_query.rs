@@ -1,8 +1,12 @@
 use cql::codec::*;
+use cql::def::Version;
 use cql::message::*;
 use cql::request::query::*;
+use cql::result::ProtError;
 use cql::types::*;
 
+use strum::IntoEnumIterator;
+
 use std::io::Cursor;
 
 #[test]
@@ -26,16 +30,25 @@ fn eq() {
 #[test]
 fn length() {
     let mut m = Query::from("");
-    assert_eq!(m.length(), 10);
+    assert_eq!(m.length(Version::V3), 7);
+    assert_eq!(m.length(Version::V4), 7);
+    assert_eq!(m.length(Version::V5), 10);
 
     m.set_query("a");
-    assert_eq!(m.length(), 11);
+    assert_eq!(m.length(Version::V4), 8);
+    assert_eq!(m.length(Version::V5), 11);
+
+    m.params_mut().set_keyspace("a");
+    assert_eq!(m.length(Version::V4), 8);
+    assert_eq!(m.length(Version::V5), 14);
+
+    m.params_mut().set_now_in_seconds(1);
+    assert_eq!(m.length(Version::V4), 8);
+    assert_eq!(m.length(Version::V5), 18);
 }
 
 #[test]
 fn serde() {
-    let mut codec = Codec::new(Cursor::new(Vec::new()));
-
     let mut a = Query::from("a");
     let mut params = QueryParams::default();
     params.set_names(vec!["v".to_string(), "i".to_string(), "n".to_string()]);
@@ -46,11 +59,51 @@ fn serde() {
     params.set_result_page_size(10);
     a.set_params(params);
 
-    a.encode(&mut codec).unwrap();
+    for version in Version::iter() {
+        let mut codec = Codec::new(Cursor::new(Vec::new()));
+        a.encode(&mut codec, version).unwrap();
+        assert_eq!(codec.io().position(), a.length(version) as u64);
+
+        codec.io().set_position(0);
+        let b = Query::decode(&mut codec, version).unwrap();
+        println!("{}", b);
+        assert_eq!(a, b);
+    }
+}
+
+#[test]
+fn keyspace() {
+    let mut a = Query::from("a");
+    a.params_mut().set_keyspace("ks");
+
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    a.encode(&mut codec, Version::V5).unwrap();
     codec.io().set_position(0);
-    let b = Query::decode(&mut codec).unwrap();
-    println!("{}", b);
+    let b = Query::decode(&mut codec, Version::V5).unwrap();
     assert_eq!(a, b);
 
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    match a.encode(&mut codec, Version::V4) {
+        Err(ProtError::UnsupportedVersion("keyspace", 4)) => {},
+        r => unreachable!("{:?}", r),
+    }
+}
+
+#[test]
+fn now_in_seconds() {
+    let mut a = Query::from("a");
+    a.params_mut().set_now_in_seconds(1_600_000_000);
+
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    a.encode(&mut codec, Version::V5).unwrap();
+    assert_eq!(&codec.io().get_ref()[7..11], &[0x00, 0x00, 0x01, 0x00]);
     codec.io().set_position(0);
+    let b = Query::decode(&mut codec, Version::V5).unwrap();
+    assert_eq!(a, b);
+
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    match a.encode(&mut codec, Version::V4) {
+        Err(ProtError::UnsupportedVersion("now_in_seconds", 4)) => {},
+        r => unreachable!("{:?}", r),
+    }
 }
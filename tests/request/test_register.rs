@@ -26,13 +26,13 @@ fn eq() {
 #[test]
 fn length() {
     let mut m = Register::new();
-    assert_eq!(m.length(), 2);
+    assert_eq!(m.length(Version::default()), 2);
 
     m.set_events(&[EventType::TopologyChange]);
-    assert_eq!(m.length(), 19);
+    assert_eq!(m.length(Version::default()), 19);
 
     m.set_events(&[EventType::TopologyChange, EventType::StatusChange]);
-    assert_eq!(m.length(), 34);
+    assert_eq!(m.length(Version::default()), 34);
 }
 
 #[test]
@@ -40,18 +40,18 @@ fn serde() {
     let mut codec = Codec::new(Cursor::new(Vec::new()));
 
     let a = Register::new();
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
     codec.io().set_position(0);
-    let b = Register::decode(&mut codec).unwrap();
+    let b = Register::decode(&mut codec, Version::default()).unwrap();
     println!("{}", b);
     assert_eq!(a, b);
 
     codec.io().set_position(0);
 
     let a = Register::from(&[EventType::TopologyChange, EventType::StatusChange]);
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
     codec.io().set_position(0);
-    let b = Register::decode(&mut codec).unwrap();
+    let b = Register::decode(&mut codec, Version::default()).unwrap();
     println!("{}", b);
     assert_eq!(a, b);
 }
@@ -1,5 +1,6 @@
 use cql::codec::*;
 use cql::compression::Compression;
+use cql::def::Version;
 use cql::message::*;
 use cql::request::startup::Startup;
 
@@ -27,13 +28,13 @@ fn eq() {
 #[test]
 fn length() {
     let mut m = Startup::new();
-    assert_eq!(m.length(), 22);
+    assert_eq!(m.length(Version::default()), 22);
 
     m.set_compression(Compression::Lz4);
-    assert_eq!(m.length(), 40);
+    assert_eq!(m.length(Version::default()), 40);
 
     m.set_compression(Compression::Snappy);
-    assert_eq!(m.length(), 43);
+    assert_eq!(m.length(Version::default()), 43);
 }
 
 #[test]
@@ -41,13 +42,13 @@ fn serde() {
     let mut codec = Codec::new(Cursor::new(Vec::new()));
 
     let a = Startup::new();
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
     assert_eq!(codec.io().get_ref().len(), 22);
     assert_eq!(codec.io().position(), 22);
 
     codec.io().set_position(0);
     assert_eq!(codec.io().position(), 0);
 
-    let b = Startup::decode(&mut codec).unwrap();
+    let b = Startup::decode(&mut codec, Version::default()).unwrap();
     assert_eq!(a, b);
 }
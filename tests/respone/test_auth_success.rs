@@ -1,4 +1,5 @@
 use cql::codec::*;
+use cql::def::Version;
 use cql::message::*;
 use cql::response::auth_success::AuthSuccess;
 
@@ -26,10 +27,10 @@ fn eq() {
 #[test]
 fn length() {
     let mut m = AuthSuccess::new();
-    assert_eq!(m.length(), 4);
+    assert_eq!(m.length(Version::default()), 4);
 
     m.set_token(Vec::from("a"));
-    assert_eq!(m.length(), 5);
+    assert_eq!(m.length(Version::default()), 5);
 }
 
 #[test]
@@ -37,8 +38,8 @@ fn serde() {
     let mut codec = Codec::new(Cursor::new(Vec::new()));
 
     let a = AuthSuccess::from(Vec::from("a"));
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
     codec.io().set_position(0);
-    let b = AuthSuccess::decode(&mut codec).unwrap();
+    let b = AuthSuccess::decode(&mut codec, Version::default()).unwrap();
     assert_eq!(a, b);
 }
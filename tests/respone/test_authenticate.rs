@@ -1,4 +1,5 @@
 use cql::codec::*;
+use cql::def::Version;
 use cql::message::*;
 use cql::response::authenticate::Authenticate;
 
@@ -26,10 +27,10 @@ fn eq() {
 #[test]
 fn length() {
     let mut m = Authenticate::new();
-    assert_eq!(m.length(), 2);
+    assert_eq!(m.length(Version::default()), 2);
 
     m.set_authenticator("AllowAllAuthenticator");
-    assert_eq!(m.length(), 23);
+    assert_eq!(m.length(Version::default()), 23);
 }
 
 #[test]
@@ -37,8 +38,8 @@ fn serde() {
     let mut codec = Codec::new(Cursor::new(Vec::new()));
 
     let a = Authenticate::from("AllowAllAuthenticator");
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
     codec.io().set_position(0);
-    let b = Authenticate::decode(&mut codec).unwrap();
+    let b = Authenticate::decode(&mut codec, Version::default()).unwrap();
     assert_eq!(a, b);
 }
@@ -1,9 +1,14 @@
 use cql::codec::*;
-use cql::def::ErrorCode;
+use cql::def::{ErrorCode, RequestFailureReason, Version, WriteType};
 use cql::message::*;
 use cql::response::error::*;
+use cql::result::ProtError;
+use cql::types::Consistency;
+
+use strum::IntoEnumIterator;
 
 use std::io::Cursor;
+use std::net::{IpAddr, Ipv4Addr};
 
 #[test]
 fn message() {
@@ -27,29 +32,114 @@ fn eq() {
 #[test]
 fn length() {
     let mut m = Error::new();
-    assert_eq!(m.length(), 6);
+    assert_eq!(m.length(Version::default()), 6);
 
     m.set_error(ErrorCode::AuthenticationError, "a");
-    assert_eq!(m.length(), 7);
+    assert_eq!(m.length(Version::default()), 7);
 }
 
 #[test]
 fn serde() {
+    for version in Version::iter() {
+        let mut codec = Codec::new(Cursor::new(Vec::new()));
+
+        let a = Error::from(ErrorCode::AuthenticationError, "a");
+        a.encode(&mut codec, version).unwrap();
+        codec.io().set_position(0);
+        let b = Error::decode(&mut codec, version).unwrap();
+        assert_eq!(a, b);
+
+        codec.io().set_position(0);
+
+        let alread_exists = AlreadyExists::new("a", "b");
+        let a = Error::from_exception(ExceptionKind::AlreadyExists(alread_exists), "a");
+        a.encode(&mut codec, version).unwrap();
+        codec.io().set_position(0);
+        let b = Error::decode(&mut codec, version).unwrap();
+        assert_eq!(a, b);
+    }
+}
+
+fn serde_exception(e: ExceptionKind, version: Version) -> Error {
+    let a = Error::from_exception(e, "a");
     let mut codec = Codec::new(Cursor::new(Vec::new()));
+    a.encode(&mut codec, version).unwrap();
+    assert_eq!(codec.io().position(), a.length(version) as u64);
 
-    let a = Error::from(ErrorCode::AuthenticationError, "a");
-    a.encode(&mut codec).unwrap();
     codec.io().set_position(0);
-    let b = Error::decode(&mut codec).unwrap();
-    assert_eq!(a, b);
+    Error::decode(&mut codec, version).unwrap()
+}
 
-    codec.io().set_position(0);
+#[test]
+fn failure() {
+    let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+    let reasonmap = vec![(addr, 0x0001), (addr, 0x0099)];
+
+    for version in Version::iter() {
+        let e = ReadFailure::new(Consistency::One, 1, 2, reasonmap.clone(), 1);
+        let b = serde_exception(ExceptionKind::ReadFailure(e), version);
+        let e = match b.exception() {
+            ExceptionKind::ReadFailure(e) => e,
+            _ => unreachable!("{:?}", b),
+        };
+        assert_eq!(e.numfailures(), 2);
+        if version >= Version::V5 {
+            assert_eq!(e.reasonmap(), &reasonmap);
+            assert_eq!(e.reasons(), vec![(addr, RequestFailureReason::ReadTooManyTombstones),
+                                         (addr, RequestFailureReason::Unknown)]);
+        } else {
+            assert!(e.reasonmap().is_empty());
+        }
+
+        let e = WriteFailure::new(Consistency::One, 1, 2, reasonmap.clone(), WriteType::Cdc);
+        let b = serde_exception(ExceptionKind::WriteFailure(e), version);
+        let e = match b.exception() {
+            ExceptionKind::WriteFailure(e) => e,
+            _ => unreachable!("{:?}", b),
+        };
+        assert_eq!(e.numfailures(), 2);
+        assert_eq!(e.write_type(), WriteType::Cdc);
+        assert_eq!(e.reasonmap().len(), if version >= Version::V5 { 2 } else { 0 });
+    }
+}
+
+#[test]
+fn write_timeout() {
+    for version in Version::iter() {
+        let mut e = WriteTimeout::new(Consistency::Serial, 1, 2, WriteType::Cas);
+        e.set_contentions(3);
+        let b = serde_exception(ExceptionKind::WriteTimeout(e), version);
+        let e = match b.exception() {
+            ExceptionKind::WriteTimeout(e) => e,
+            _ => unreachable!("{:?}", b),
+        };
+        assert_eq!(e.write_type(), WriteType::Cas);
+        assert_eq!(e.contentions(), &if version >= Version::V5 { Some(3) } else { None });
+    }
+}
+
+#[test]
+fn cas_write_unknown() {
+    let e = CasWriteUnknown::new(Consistency::Serial, 1, 2);
+    let b = serde_exception(ExceptionKind::CasWriteUnknown(e), Version::V5);
+    assert_eq!(b.code(), ErrorCode::CasWriteUnknown);
+    assert_eq!(b.exception(), &ExceptionKind::CasWriteUnknown(CasWriteUnknown::new(Consistency::Serial, 1, 2)));
 
-    let alread_exists = AlreadyExists::new("a", "b");
-    let a = Error::from_exception(ExceptionKind::AlreadyExists(alread_exists), "a");
-    a.encode(&mut codec).unwrap();
+    let a = Error::from(ErrorCode::CdcWriteFailure, "a");
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    a.encode(&mut codec, Version::V5).unwrap();
     codec.io().set_position(0);
-    let b = Error::decode(&mut codec).unwrap();
-    assert_eq!(a, b);
+    assert_eq!(Error::decode(&mut codec, Version::V5).unwrap(), a);
 }
 
+#[test]
+fn invalid() {
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    codec.write_int(0x9999).unwrap();
+    codec.write_string(&"a".to_string()).unwrap();
+    codec.io().set_position(0);
+    match Error::decode(&mut codec, Version::default()) {
+        Err(ProtError::UnknownErrorCode(0x9999)) => {},
+        r => unreachable!("{:?}", r),
+    }
+}
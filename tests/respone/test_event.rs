@@ -2,6 +2,7 @@ use cql::codec::*;
 use cql::def::*;
 use cql::message::*;
 use cql::response::event::*;
+use cql::result::ProtError;
 
 use std::{
     io::Cursor,
@@ -52,7 +53,7 @@ fn eq() {
 #[test]
 fn length() {
     let m = default();
-    assert_eq!(m.length(), 48);
+    assert_eq!(m.length(Version::default()), 48);
 }
 
 #[test]
@@ -60,16 +61,37 @@ fn serde() {
     let mut codec = Codec::new(Cursor::new(Vec::new()));
 
     let a = default();
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
     codec.io().set_position(0);
-    let b = Event::decode(&mut codec).unwrap();
+    let b = Event::decode(&mut codec, Version::default()).unwrap();
     assert_eq!(a, b);
 
     codec.io().set_position(0);
 
     let a = schema_change();
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
     codec.io().set_position(0);
-    let b = Event::decode(&mut codec).unwrap();
+    let b = Event::decode(&mut codec, Version::default()).unwrap();
     assert_eq!(a, b);
 }
+
+#[test]
+fn invalid() {
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    codec.write_string(&"UNKNOWN".to_string()).unwrap();
+    codec.io().set_position(0);
+    match Event::decode(&mut codec, Version::default()) {
+        Err(ProtError::UnknownEventType(ref s)) => assert_eq!(s, "UNKNOWN"),
+        r => unreachable!("{:?}", r),
+    }
+
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    codec.write_string(&"STATUS_CHANGE".to_string()).unwrap();
+    codec.write_string(&"SIDEWAYS".to_string()).unwrap();
+    codec.write_inet(&SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 80)).unwrap();
+    codec.io().set_position(0);
+    match Event::decode(&mut codec, Version::default()) {
+        Err(ProtError::InvalidEnumValue(_, ref s)) => assert_eq!(s, "SIDEWAYS"),
+        r => unreachable!("{:?}", r),
+    }
+}
@@ -1,4 +1,5 @@
 use cql::codec::*;
+use cql::def::Version;
 use cql::message::*;
 use cql::response::ready::Ready;
 
@@ -15,9 +16,9 @@ fn serde() {
     let mut codec = Codec::new(Cursor::new(Vec::new()));
 
     let a = Ready::new();
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
     assert_eq!(codec.io().get_ref().len(), 0);
 
-    let b = Ready::decode(&mut codec).unwrap();
+    let b = Ready::decode(&mut codec, Version::default()).unwrap();
     assert_eq!(a, b);
 }
@@ -1,6 +1,11 @@
 use cql::codec::*;
+use cql::def::Version;
 use cql::message::*;
 use cql::response::result::*;
+use cql::result::ProtError;
+use cql::types::*;
+
+use strum::IntoEnumIterator;
 
 use std::io::Cursor;
 
@@ -8,12 +13,31 @@ fn void() -> Result {
     Result::new(ResultBody::Void(Void::default()))
 }
 
+fn col_specs() -> Vec<ColSpec> {
+    vec![ColSpec::new("id", Opt::new(OptIds::Int)), ColSpec::new("name", Opt::new(OptIds::Varchar))]
+}
+
 fn rows() -> Result {
-    Result::new(ResultBody::Rows(Rows::default()))
+    let mut metadata = RowsMetadata::default();
+    metadata.set_paging_state(Some(vec![1, 2, 3]));
+    metadata.set_global_table_spec(GlobalTableSpec::new("ks", "table"));
+    metadata.set_col_specs(col_specs());
+    let content = vec![vec![marshal_int(&1).unwrap(), marshal_varchar("a").unwrap()], vec![None, None]];
+    Result::new(ResultBody::Rows(Rows::new(metadata, content)))
 }
 
 fn prepared() -> Result {
-    Result::new(ResultBody::Prepared(Prepared::default()))
+    let mut metadata = PreparedMetadata::default();
+    metadata.set_pk_indices(vec![0]);
+    metadata.set_global_table_spec(GlobalTableSpec::new("ks", "table"));
+    metadata.set_col_specs(col_specs());
+    Result::new(ResultBody::Prepared(Prepared::new(vec![0, 1], metadata, RowsMetadata::default())))
+}
+
+fn prepared_v5() -> Prepared {
+    let mut prepared = Prepared::new(vec![0, 1], PreparedMetadata::default(), RowsMetadata::default());
+    prepared.set_result_metadata_id(vec![2, 3]);
+    prepared
 }
 
 fn set_keyspace() -> Result {
@@ -44,43 +68,110 @@ fn eq() {
 #[test]
 fn length() {
     let m = default();
-    assert_eq!(m.length(), 4);
+    assert_eq!(m.length(Version::default()), 4);
 
     let m = set_keyspace();
-    assert_eq!(m.length(), 7);
+    assert_eq!(m.length(Version::default()), 7);
 }
 
 #[test]
 fn serde() {
-    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    for version in Version::iter() {
+        let mut results = vec![default(), set_keyspace(), rows()];
+        if version >= Version::V4 {
+            results.push(prepared());
+        }
+
+        for a in results {
+            let mut codec = Codec::new(Cursor::new(Vec::new()));
+            a.encode(&mut codec, version).unwrap();
+            assert_eq!(codec.io().position(), a.length(version) as u64);
+
+            codec.io().set_position(0);
+            let b = Result::decode(&mut codec, version).unwrap();
+            assert_eq!(a, b);
+        }
+    }
+}
 
-    let a = default();
-    a.encode(&mut codec).unwrap();
-    codec.io().set_position(0);
-    let b = Result::decode(&mut codec).unwrap();
-    assert_eq!(a, b);
+#[test]
+fn prepared_v3() {
+    let a = prepared();
+    assert_eq!(a.length(Version::V3) + 6, a.length(Version::V4));
 
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    a.encode(&mut codec, Version::V3).unwrap();
     codec.io().set_position(0);
+    let b = Result::decode(&mut codec, Version::V3).unwrap();
+    match b.body() {
+        ResultBody::Prepared(prepared) => assert!(prepared.metadata().pk_indices().is_empty()),
+        _ => unreachable!("{:?}", b),
+    }
+}
 
-    let a = set_keyspace();
-    a.encode(&mut codec).unwrap();
-    codec.io().set_position(0);
-    let b = Result::decode(&mut codec).unwrap();
-    assert_eq!(a, b);
+#[test]
+fn result_metadata_id() {
+    let a = prepared_v5();
+    assert_eq!(a.length(Version::V4) + 4, a.length(Version::V5));
 
-    codec.io().set_position(0);
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    a.encode(&mut codec, Version::V5).unwrap();
+    assert_eq!(&codec.io().get_ref()[..8], &[0, 2, 0, 1, 0, 2, 2, 3]);
 
-    let a = rows();
-    a.encode(&mut codec).unwrap();
     codec.io().set_position(0);
-    let b = Result::decode(&mut codec).unwrap();
+    let b = Prepared::decode(&mut codec, Version::V5).unwrap();
     assert_eq!(a, b);
+}
+
+#[test]
+fn metadata_changed() {
+    let mut prepared = prepared_v5();
+    assert!(!prepared.update_result_metadata(RowsMetadata::default()));
+    assert_eq!(prepared.result_metadata_id(), &vec![2, 3]);
+
+    let mut metadata = RowsMetadata::default();
+    metadata.set_new_metadata_id(vec![4, 5]);
+    metadata.set_global_table_spec(GlobalTableSpec::new("ks", "table"));
+    metadata.set_col_specs(col_specs());
+    let rows = Rows::new(metadata, Vec::new());
 
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    rows.encode(&mut codec, Version::V5).unwrap();
     codec.io().set_position(0);
+    let (metadata, _) = Rows::decode(&mut codec, Version::V5).unwrap().into_parts();
+
+    assert!(prepared.update_result_metadata(metadata));
+    assert_eq!(prepared.result_metadata_id(), &vec![4, 5]);
+    assert_eq!(prepared.result_metadata().col_specs(), &col_specs());
+    assert_eq!(prepared.result_metadata().new_metadata_id(), &None);
+}
+
+#[test]
+fn no_metadata() {
+    let mut metadata = RowsMetadata::default();
+    metadata.set_no_metadata();
+    metadata.set_columns_count(2);
+    let content = vec![vec![marshal_int(&1).unwrap(), marshal_varchar("a").unwrap()]];
+    let a = Rows::new(metadata, content);
+
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    a.encode(&mut codec, Version::V5).unwrap();
+    assert_eq!(codec.io().position(), a.length(Version::V5) as u64);
 
-    let a = prepared();
-    a.encode(&mut codec).unwrap();
     codec.io().set_position(0);
-    let b = Result::decode(&mut codec).unwrap();
+    let b = Rows::decode(&mut codec, Version::V5).unwrap();
+    assert_eq!(b.metadata().columns_count(), 2);
+    assert_eq!(b.content().len(), 1);
     assert_eq!(a, b);
 }
+
+#[test]
+fn invalid() {
+    let mut codec = Codec::new(Cursor::new(Vec::new()));
+    codec.write_int(0x0099).unwrap();
+    codec.io().set_position(0);
+    match Result::decode(&mut codec, Version::default()) {
+        Err(ProtError::UnknownResultKind(0x0099)) => {},
+        r => unreachable!("{:?}", r),
+    }
+}
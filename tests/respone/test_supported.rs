@@ -1,4 +1,5 @@
 use cql::codec::*;
+use cql::def::Version;
 use cql::message::*;
 use cql::response::supported::Supported;
 
@@ -26,10 +27,10 @@ fn eq() {
 #[test]
 fn length() {
     let mut m = Supported::new();
-    assert_eq!(m.length(), 2);
+    assert_eq!(m.length(Version::default()), 2);
 
     m.add_option("a".to_string(), vec!["b".to_string()]);
-    assert_eq!(m.length(), 10);
+    assert_eq!(m.length(Version::default()), 10);
 }
 
 #[test]
@@ -38,9 +39,9 @@ fn serde() {
 
     let mut a = Supported::new();
     a.add_option("a".to_string(), vec!["b".to_string()]);
-    a.encode(&mut codec).unwrap();
+    a.encode(&mut codec, Version::default()).unwrap();
 
     codec.io().set_position(0);
-    let b = Supported::decode(&mut codec).unwrap();
+    let b = Supported::decode(&mut codec, Version::default()).unwrap();
     assert_eq!(a, b);
 }
@@ -0,0 +1,254 @@
+use cql::compression::*;
+use cql::def::Version;
+use cql::message::Message;
+use cql::request::Startup;
+use cql::response::Supported;
+
+use maplit::hashmap;
+
+use std::str::FromStr;
+
+fn supported(compressions: &[&str]) -> Supported {
+    Supported::from(hashmap!{"COMPRESSION".to_string() => compressions.iter().map(|c| c.to_string()).collect()})
+}
+
+#[test]
+fn compression() {
+    assert_eq!(Compression::from_str("snappy").unwrap(), Compression::Snappy);
+    assert_eq!(Compression::from_str("lz4").unwrap(), Compression::Lz4);
+    assert_eq!(Compression::Snappy.compressor().is_some(), cfg!(feature = "snappy"));
+    assert_eq!(Compression::Lz4.compressor().is_some(), cfg!(feature = "lz4"));
+}
+
+#[test]
+fn negotiate_fallback() {
+    let mut startup = Startup::new();
+    assert!(negotiate(&Supported::new(), &PREFERRED_COMPRESSIONS, Version::V4, &mut startup).is_none());
+    assert!(negotiate(&supported(&["deflate"]), &PREFERRED_COMPRESSIONS, Version::V4, &mut startup).is_none());
+    assert!(negotiate(&supported(&["lz4", "snappy"]), &[], Version::V4, &mut startup).is_none());
+    assert_eq!(startup.compression(), None);
+}
+
+#[cfg(all(feature = "lz4", feature = "snappy"))]
+#[test]
+fn negotiate_preference() {
+    let mut startup = Startup::new();
+    assert!(negotiate(&supported(&["snappy", "LZ4"]), &PREFERRED_COMPRESSIONS, Version::V4, &mut startup).is_some());
+    assert_eq!(startup.compression(), Some(Compression::Lz4));
+
+    let mut startup = Startup::new();
+    let preference = [Compression::Snappy, Compression::Lz4];
+    assert!(negotiate(&supported(&["snappy", "lz4"]), &preference, Version::V4, &mut startup).is_some());
+    assert_eq!(startup.compression(), Some(Compression::Snappy));
+
+    let mut startup = Startup::new();
+    assert!(negotiate(&supported(&["snappy", "lz4"]), &preference, Version::V5, &mut startup).is_some());
+    assert_eq!(startup.compression(), Some(Compression::Lz4));
+
+    let mut startup = Startup::new();
+    assert!(negotiate(&supported(&["snappy"]), &PREFERRED_COMPRESSIONS, Version::V5, &mut startup).is_none());
+    assert_eq!(startup.compression(), None);
+}
+
+#[cfg(feature = "lz4")]
+mod lz4 {
+    use cql::compression::*;
+    use cql::def::{Flags, Version};
+    use cql::frame::Frame;
+    use cql::message::{Message, MessageKind};
+    use cql::request::Query;
+    use cql::response::Ready;
+    use cql::segment::{Segment, SegmentCodec};
+
+    use strum::IntoEnumIterator;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn serde() {
+        let mut lz4 = Lz4::new();
+        let a = "a".repeat(1024);
+        let mut v = Vec::new();
+        lz4.compress(a.as_bytes(), &mut v).unwrap();
+        assert_eq!(&v[..4], &[0x00, 0x00, 0x04, 0x00]);
+        assert!(v.len() < a.len());
+        let mut b = b"x".to_vec();
+        lz4.decompress(&v, &mut b).unwrap();
+        assert_eq!(&b[1..], a.as_bytes());
+
+        let mut v = Vec::new();
+        lz4.compress(&[], &mut v).unwrap();
+        let mut b = Vec::new();
+        lz4.decompress(&v, &mut b).unwrap();
+        assert!(b.is_empty());
+
+        assert!(lz4.decompress(&[0x00, 0x00], &mut b).is_err());
+    }
+
+    #[test]
+    fn max_length() {
+        let mut lz4 = Lz4::new();
+        let mut v = Vec::new();
+        lz4.compress("a".repeat(1024).as_bytes(), &mut v).unwrap();
+
+        lz4.set_max_length(1023);
+        assert_eq!(lz4.max_length(), 1023);
+        let mut b = Vec::new();
+        assert!(lz4.decompress(&v, &mut b).is_err());
+        assert!(b.is_empty());
+
+        v[..4].copy_from_slice(&[0x7F, 0xFF, 0xFF, 0xFF]);
+        assert!(Lz4::new().decompress(&v, &mut b).is_err());
+    }
+
+    #[test]
+    fn segment() {
+        let mut codec = SegmentCodec::new(Compression::Lz4.compressor());
+        let payload = "a".repeat(1024).into_bytes();
+        let mut v = Vec::new();
+        codec.encode(&mut v, &Segment::new(payload.clone(), true)).unwrap();
+        assert!(v.len() < payload.len());
+
+        let segment = codec.decode(&mut Cursor::new(v)).unwrap();
+        assert_eq!(segment.payload(), payload.as_slice());
+    }
+
+    #[test]
+    fn frame() {
+        for version in Version::iter() {
+            let mut frame = Frame::new(version, 0, Cursor::new(Vec::new()), Compression::Lz4.compressor());
+            frame.encode(&Ready::new()).unwrap();
+            let a = Query::from("a".repeat(1024).as_str());
+            frame.encode(&a).unwrap();
+
+            let v = frame.io_mut().get_ref().clone();
+            assert!(v.len() < 1024);
+
+            let mut frame = Frame::new(version, 0, Cursor::new(v), Compression::Lz4.compressor());
+            frame.decode().unwrap();
+            let envelope = frame.decode().unwrap();
+            assert_eq!(Flags::Compression.is_set(envelope.flags()), version < Version::V5);
+            let m = envelope.into_message();
+            let b = match m {
+                MessageKind::Query(b) => b,
+                _ => unreachable!("{:?}", m),
+            };
+            assert_eq!(a, b);
+        }
+    }
+}
+
+#[cfg(feature = "snappy")]
+mod snappy {
+    use cql::compression::*;
+    use cql::def::{Flags, Version};
+    use cql::frame::Frame;
+    use cql::message::{Message, MessageKind};
+    use cql::request::Query;
+    use cql::response::Ready;
+    use cql::result::ProtError;
+
+    use strum::IntoEnumIterator;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn serde() {
+        let mut snappy = Snappy::new();
+        let a = "a".repeat(1024);
+        let mut v = Vec::new();
+        snappy.compress(a.as_bytes(), &mut v).unwrap();
+        assert!(v.len() < a.len());
+        let mut b = b"x".to_vec();
+        snappy.decompress(&v, &mut b).unwrap();
+        assert_eq!(&b[1..], a.as_bytes());
+
+        let mut b = Vec::new();
+        assert!(snappy.decompress(&[0xFF, 0xFF, 0xFF], &mut b).is_err());
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn max_length() {
+        let mut snappy = Snappy::new();
+        let mut v = Vec::new();
+        snappy.compress("a".repeat(1024).as_bytes(), &mut v).unwrap();
+
+        snappy.set_max_length(1023);
+        assert_eq!(snappy.max_length(), 1023);
+        let mut b = Vec::new();
+        assert!(snappy.decompress(&v, &mut b).is_err());
+        assert!(b.is_empty());
+
+        let v = [0xFF, 0xFF, 0xFF, 0xFF, 0x07, 0x00];
+        assert!(Snappy::new().decompress(&v, &mut b).is_err());
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn frame_limit() {
+        let v = vec![0x84, 0x01, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x06, 0xFF, 0xFF, 0xFF, 0xFF, 0x07, 0x00];
+        let mut frame = Frame::new(Version::V4, 1, Cursor::new(v.clone()), Compression::Snappy.compressor());
+        match frame.decode() {
+            Err(ProtError::LimitExceeded("decompressed length", 0x7FFF_FFFF)) => {},
+            r => unreachable!("{:?}", r),
+        }
+
+        let mut v = v;
+        v[9..14].copy_from_slice(&[0x80, 0x08, 0x00, 0x00, 0x00]);
+        let mut frame = Frame::new(Version::V4, 1, Cursor::new(v), Compression::Snappy.compressor());
+        frame.limits_mut().set_max_frame_length(1023);
+        match frame.decode() {
+            Err(ProtError::LimitExceeded("decompressed length", 1024)) => {},
+            r => unreachable!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn frame() {
+        for version in Version::iter() {
+            let mut frame = Frame::new(version, 0, Cursor::new(Vec::new()), Compression::Snappy.compressor());
+            frame.encode(&Ready::new()).unwrap();
+            let a = Query::from("a".repeat(1024).as_str());
+            frame.encode(&a).unwrap();
+
+            let v = frame.io_mut().get_ref().clone();
+            assert!(v.len() < 1024);
+
+            let mut frame = Frame::new(version, 0, Cursor::new(v), Compression::Snappy.compressor());
+            frame.decode().unwrap();
+            let envelope = frame.decode().unwrap();
+            assert_eq!(Flags::Compression.is_set(envelope.flags()), version < Version::V5);
+            let m = envelope.into_message();
+            let b = match m {
+                MessageKind::Query(b) => b,
+                _ => unreachable!("{:?}", m),
+            };
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn min_compress_length() {
+        let mut frame = Frame::new(Version::V4, 0, Cursor::new(Vec::new()), Compression::Snappy.compressor());
+        frame.set_min_compress_length(64);
+        assert_eq!(frame.min_compress_length(), 64);
+
+        let a = Query::from("a");
+        frame.encode(&a).unwrap();
+        let b = Query::from("a".repeat(1024).as_str());
+        frame.encode(&b).unwrap();
+
+        let v = frame.io_mut().get_ref().clone();
+        let mut frame = Frame::new(Version::V4, 0, Cursor::new(v), Compression::Snappy.compressor());
+        for q in [a, b] {
+            let envelope = frame.decode().unwrap();
+            assert_eq!(Flags::Compression.is_set(envelope.flags()), q.query().len() >= 64);
+            let m = envelope.into_message();
+            match m {
+                MessageKind::Query(m) => assert_eq!(m, q),
+                _ => unreachable!("{:?}", m),
+            }
+        }
+    }
+}
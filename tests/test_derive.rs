@@ -0,0 +1,121 @@
+#![cfg(feature = "derive")]
+
+use cql::response::result::*;
+use cql::result::ProtError;
+use cql::row::{FromRow, IntoValues};
+use cql::types::*;
+
+#[derive(Debug, FromRow, IntoValues, PartialEq)]
+struct User {
+    id: i32,
+    #[cql(rename = "user_name")]
+    name: String,
+    address: Option<Address>,
+}
+
+#[derive(Debug, FromUdt, IntoUdt, PartialEq)]
+struct Address {
+    street: String,
+    zip: Option<i32>,
+}
+
+fn address_type() -> Opt {
+    Opt {
+        id: OptIds::Udt,
+        value: OptValue::Udt(OptUdt {
+            ks: "ks".to_string(),
+            name: "address".to_string(),
+            fields: vec![("street".to_string(), Opt::new(OptIds::Varchar)), ("zip".to_string(), Opt::new(OptIds::Int))],
+        }),
+    }
+}
+
+fn col_specs() -> Vec<ColSpec> {
+    vec![
+        ColSpec::new("user_name", Opt::new(OptIds::Varchar)),
+        ColSpec::new("id", Opt::new(OptIds::Int)),
+        ColSpec::new("address", address_type()),
+    ]
+}
+
+fn user() -> User {
+    User {
+        id: 1,
+        name: "a".to_string(),
+        address: Some(Address {
+            street: "b".to_string(),
+            zip: None,
+        }),
+    }
+}
+
+fn into_bytes(v: Value) -> Bytes {
+    match v {
+        Value::Some(v) => Some(v),
+        _ => None,
+    }
+}
+
+#[test]
+fn row() {
+    let a = user();
+    let values = a.to_values(&col_specs()).unwrap();
+    assert_eq!(values[0], Value::Some(b"a".to_vec()));
+    assert_eq!(values[1], Value::Some(vec![0, 0, 0, 1]));
+
+    let row: Vec<Bytes> = values.into_iter().map(into_bytes).collect();
+    let mut metadata = RowsMetadata::default();
+    metadata.set_col_specs(col_specs());
+    let rows = Rows::new(metadata, vec![row]);
+    assert_eq!(rows.rows_as::<User>().unwrap(), vec![a]);
+}
+
+#[test]
+fn udt() {
+    let a = user().address.unwrap();
+    let v = into_bytes(a.to_cql(&address_type()).unwrap());
+    assert_eq!(v, Some(vec![0, 0, 0, 1, b'b', 0xFF, 0xFF, 0xFF, 0xFF]));
+    assert_eq!(Address::from_cql(&address_type(), &v).unwrap(), a);
+
+    let v = Some(vec![0, 0, 0, 1, b'b']);
+    assert_eq!(Address::from_cql(&address_type(), &v).unwrap(), a);
+}
+
+#[test]
+fn missing() {
+    let col_specs = vec![ColSpec::new("id", Opt::new(OptIds::Int))];
+    match User::from_row(&col_specs, &[Some(vec![0, 0, 0, 1])]) {
+        Err(ProtError::MissingField(ref name)) if name == "user_name" => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let col_specs = vec![ColSpec::new("id", Opt::new(OptIds::Int)), ColSpec::new("other", Opt::new(OptIds::Int))];
+    match user().to_values(&col_specs) {
+        Err(ProtError::MissingField(ref name)) if name == "other" => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let col_specs = vec![ColSpec::new("id", Opt::new(OptIds::Int))];
+    match user().to_values(&col_specs) {
+        Err(ProtError::UnknownField(ref name)) if name == "user_name" => {},
+        r => unreachable!("{:?}", r),
+    }
+}
+
+#[test]
+fn mistyped() {
+    let mut col_specs = col_specs();
+    col_specs[1] = ColSpec::new("id", Opt::new(OptIds::Bigint));
+    match user().to_values(&col_specs) {
+        Err(ProtError::InvalidField(ref name, ref e)) if name == "id" => {
+            assert!(matches!(**e, ProtError::TypeMismatch(..)));
+        },
+        r => unreachable!("{:?}", r),
+    }
+
+    let row = vec![Some(b"a".to_vec()), Some(vec![0, 0, 0, 1]), None];
+    match User::from_row(&col_specs, &row) {
+        Err(e @ ProtError::InvalidField(..)) => assert!(e.to_string().starts_with("invalid field id: type mismatch")),
+        r => unreachable!("{:?}", r),
+    }
+}
@@ -0,0 +1,175 @@
+#![cfg(feature = "dse")]
+
+use cql::dse::*;
+use cql::result::*;
+use cql::types::*;
+
+use chrono::prelude::*;
+
+const POINT: [u8; 21] = [
+    0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF0, 0x3F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x40,
+];
+const POINT_BE: [u8; 21] = [
+    0x00, 0x00, 0x00, 0x00, 0x01, 0x3F, 0xF0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00,
+];
+
+fn bound(y: i32, m: u32, d: u32, precision: DateRangePrecision) -> DateRangeBound {
+    DateRangeBound::Bounded(Utc.ymd(y, m, d).and_hms(0, 0, 0), precision)
+}
+
+fn bytes(v: Value) -> Bytes {
+    match v {
+        Value::Some(v) => Some(v),
+        _ => unreachable!("{:?}", v),
+    }
+}
+
+#[test]
+fn point() {
+    let ty = Opt::custom(POINT_TYPE);
+    let p = Point::new(1.0, 2.0);
+    assert_eq!(p.to_cql(&ty).unwrap(), Value::Some(POINT.to_vec()));
+    assert_eq!(Point::from_cql(&ty, &Some(POINT.to_vec())).unwrap(), p);
+    assert_eq!(Point::from_cql(&ty, &Some(POINT_BE.to_vec())).unwrap(), p);
+
+    assert_eq!(p.to_string(), "POINT (1 2)");
+    assert_eq!("point(1 2)".parse::<Point>().unwrap(), p);
+    assert!("POINT EMPTY".parse::<Point>().is_err());
+
+    assert!(Point::from_cql(&ty, &Some(POINT[..20].to_vec())).is_err());
+    assert!(p.to_cql(&Opt::custom(POLYGON_TYPE)).is_err());
+}
+
+#[test]
+fn line_string() {
+    let ty = Opt::custom(LINE_STRING_TYPE);
+    let a = LineString::new(vec![Point::new(30.0, 10.0), Point::new(10.0, 30.0), Point::new(40.5, 40.0)]);
+    assert_eq!(a.to_string(), "LINESTRING (30 10, 10 30, 40.5 40)");
+    assert_eq!(a.to_string().parse::<LineString>().unwrap(), a);
+    assert_eq!(LineString::default().to_string(), "LINESTRING EMPTY");
+
+    let v = bytes(a.to_cql(&ty).unwrap());
+    assert_eq!(v.as_ref().unwrap().len(), 9 + 3 * 16);
+    assert_eq!(LineString::from_cql(&ty, &v).unwrap(), a);
+    assert!(Point::from_cql(&Opt::custom(POINT_TYPE), &v).is_err());
+}
+
+#[test]
+fn polygon() {
+    let ty = Opt::custom(POLYGON_TYPE);
+    let ring = vec![Point::new(30.0, 10.0), Point::new(40.0, 40.0), Point::new(20.0, 40.0), Point::new(30.0, 10.0)];
+    let hole = vec![Point::new(30.0, 20.0), Point::new(35.0, 35.0), Point::new(30.0, 20.0)];
+    let a = Polygon::new(vec![ring, hole]);
+
+    let s = "POLYGON ((30 10, 40 40, 20 40, 30 10), (30 20, 35 35, 30 20))";
+    assert_eq!(a.to_string(), s);
+    assert_eq!(s.parse::<Polygon>().unwrap(), a);
+    assert_eq!("POLYGON EMPTY".parse::<Polygon>().unwrap(), Polygon::default());
+
+    let v = bytes(a.to_cql(&ty).unwrap());
+    assert_eq!(Polygon::from_cql(&ty, &v).unwrap(), a);
+}
+
+#[test]
+fn date_range() {
+    let ty = Opt::custom(DATE_RANGE_TYPE);
+    let lower = bound(2017, 1, 1, DateRangePrecision::Day);
+    let ranges = vec![
+        (DateRange::single(lower), "2017-01-01", 10),
+        (DateRange::range(lower, bound(2018, 1, 1, DateRangePrecision::Year)), "[2017-01-01 TO 2018]", 19),
+        (DateRange::range(lower, DateRangeBound::Unbounded), "[2017-01-01 TO *]", 10),
+        (DateRange::range(DateRangeBound::Unbounded, lower), "[* TO 2017-01-01]", 10),
+        (DateRange::range(DateRangeBound::Unbounded, DateRangeBound::Unbounded), "[* TO *]", 1),
+        (DateRange::single(DateRangeBound::Unbounded), "*", 1),
+    ];
+
+    for (i, (a, s, len)) in ranges.into_iter().enumerate() {
+        assert_eq!(a.to_string(), s);
+        assert_eq!(s.parse::<DateRange>().unwrap(), a);
+
+        let v = bytes(a.to_cql(&ty).unwrap()).unwrap();
+        assert_eq!(v[0], i as u8);
+        assert_eq!(v.len(), len);
+        assert_eq!(DateRange::from_cql(&ty, &Some(v)).unwrap(), a);
+    }
+
+    let v = vec![0x00, 0x00, 0x00, 0x01, 0x59, 0x52, 0x2D, 0x08, 0x00, 0x02];
+    assert_eq!(DateRange::from_cql(&ty, &Some(v)).unwrap().to_string(), "2016-12-31");
+
+    let a: DateRange = "2017-02-03T04:05:06.789Z".parse().unwrap();
+    assert_eq!(a.to_string(), "2017-02-03T04:05:06.789");
+    assert!("2017-13".parse::<DateRange>().is_err());
+    assert!("[2017 2018]".parse::<DateRange>().is_err());
+
+    let v = vec![0x00, 0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02];
+    match DateRange::from_cql(&ty, &Some(v)) {
+        Err(ProtError::InvalidFormat(..)) => {},
+        r => unreachable!("{:?}", r),
+    }
+}
+
+#[test]
+fn date_range_upper_bound() {
+    let end = |y, m, d, precision| DateRangeBound::Bounded(Utc.ymd(y, m, d).and_hms_milli(23, 59, 59, 999), precision);
+
+    let a: DateRange = "[2020 TO 2021]".parse().unwrap();
+    assert_eq!(a.lower, bound(2020, 1, 1, DateRangePrecision::Year));
+    assert_eq!(a.upper, Some(end(2021, 12, 31, DateRangePrecision::Year)));
+    assert_eq!(a.to_string(), "[2020 TO 2021]");
+
+    let a: DateRange = "[2020-01 TO 2020-02]".parse().unwrap();
+    assert_eq!(a.upper, Some(end(2020, 2, 29, DateRangePrecision::Month)));
+
+    let a: DateRange = "[* TO 2020-12-31T10]".parse().unwrap();
+    let upper = DateRangeBound::Bounded(Utc.ymd(2020, 12, 31).and_hms_milli(10, 59, 59, 999), DateRangePrecision::Hour);
+    assert_eq!(a.upper, Some(upper));
+
+    let a: DateRange = "[2020 TO 2020-06-01T10:15:30.123]".parse().unwrap();
+    let t = Utc.ymd(2020, 6, 1).and_hms_milli(10, 15, 30, 123);
+    let upper = DateRangeBound::Bounded(t, DateRangePrecision::Millisecond);
+    assert_eq!(a.upper, Some(upper));
+
+    let ty = Opt::custom(DATE_RANGE_TYPE);
+    let a: DateRange = "[2020-01-01 TO 2020-01-31]".parse().unwrap();
+    let v = bytes(a.to_cql(&ty).unwrap());
+    assert_eq!(DateRange::from_cql(&ty, &v).unwrap(), a);
+}
+
+#[test]
+fn date_range_signed_year() {
+    let ty = Opt::custom(DATE_RANGE_TYPE);
+    let a: DateRange = "-0044-03-15".parse().unwrap();
+    assert_eq!(a.lower, bound(-44, 3, 15, DateRangePrecision::Day));
+    assert_eq!(a.to_string(), "-0044-03-15");
+    let v = bytes(a.to_cql(&ty).unwrap());
+    assert_eq!(DateRange::from_cql(&ty, &v).unwrap(), a);
+
+    let a: DateRange = "[-0100 TO +10000-01-01T00:00]".parse().unwrap();
+    assert_eq!(a.lower, bound(-100, 1, 1, DateRangePrecision::Year));
+    assert_eq!(a.to_string(), "[-0100 TO +10000-01-01T00:00]");
+    assert_eq!(a.to_string().parse::<DateRange>().unwrap(), a);
+    assert!("--0044".parse::<DateRange>().is_err());
+}
+
+#[test]
+fn registry() {
+    register();
+
+    let ty = Opt::custom(POINT_TYPE);
+    let v = unmarshal(&ty, &POINT.to_vec()).unwrap();
+    assert_eq!(v, DataTypes::Custom(Custom::new(POINT_TYPE, DataTypes::Varchar("POINT (1 2)".to_string()))));
+    assert_eq!(marshal(&v).unwrap(), Some(POINT.to_vec()));
+
+    let ty = Opt::custom(DATE_RANGE_TYPE);
+    let v = DataTypes::Custom(Custom::new(DATE_RANGE_TYPE, DataTypes::Varchar("[2017 TO *]".to_string())));
+    let bytes = marshal(&v).unwrap().unwrap();
+    assert_eq!(unmarshal(&ty, &bytes).unwrap(), v);
+
+    let v = DataTypes::Custom(Custom::new(POINT_TYPE, DataTypes::Int(1)));
+    match marshal(&v) {
+        Err(ProtError::TypeMismatch(..)) => {},
+        r => unreachable!("{:?}", r),
+    }
+}
@@ -1,10 +1,18 @@
+#[macro_use]
+extern crate maplit;
+
+use cql::codec::{Codec, Decodable};
 use cql::compression::Compression;
-use cql::def::Version;
+use cql::def::{Flags, Opcode, Version};
 use cql::frame::Frame;
 use cql::message::{Message, MessageKind};
-use cql::request::Startup;
+use cql::result::ProtError;
+use cql::request::{Query, Startup};
+
+use strum::IntoEnumIterator;
+use uuid::Uuid;
 
-use std::io::Cursor;
+use std::io::{self, Cursor};
 
 #[test]
 fn serde() {
@@ -14,7 +22,9 @@ fn serde() {
     frame.encode(&a).unwrap();
 
     frame.io_mut().set_position(0);
-    let (stream_id, b) = frame.decode().unwrap();
+    let envelope = frame.decode().unwrap();
+    let stream_id = envelope.stream_id();
+    let b = envelope.into_message();
     let b = match b {
         MessageKind::Startup(b) => b,
         _ => unreachable!("{:?}", b),
@@ -26,3 +36,200 @@ fn serde() {
     a.set_compression(Compression::Lz4);
     assert_ne!(a, b);
 }
+
+#[test]
+fn query() {
+    for version in Version::iter() {
+        let mut frame = Frame::new(version, 1, Cursor::new(Vec::new()), None);
+
+        let mut a = Query::from("SELECT * FROM a");
+        a.params_mut().set_result_page_size(10);
+        frame.encode(&a).unwrap();
+
+        frame.io_mut().set_position(0);
+        let envelope = frame.decode().unwrap();
+        let stream_id = envelope.stream_id();
+        let b = envelope.into_message();
+        let b = match b {
+            MessageKind::Query(b) => b,
+            _ => unreachable!("{:?}", b),
+        };
+
+        assert_eq!(stream_id, 1);
+        assert_eq!(a, b);
+    }
+}
+
+#[test]
+fn custom_payload() {
+    let payload = hashmap!{"a".to_string() => Some(vec![1, 2]), "b".to_string() => None};
+    for version in Version::iter() {
+        let mut frame = Frame::new(version, 1, Cursor::new(Vec::new()), None);
+
+        let mut a = Query::from("SELECT * FROM a");
+        a.set_custom_payload(payload.clone());
+        frame.encode(&a).unwrap();
+
+        frame.io_mut().set_position(0);
+        let envelope = frame.decode().unwrap();
+        let custom_payload = envelope.custom_payload().clone();
+        let b = envelope.into_message();
+        let b = match b {
+            MessageKind::Query(b) => b,
+            _ => unreachable!("{:?}", b),
+        };
+
+        assert_eq!(custom_payload, None);
+        if version >= Version::V4 {
+            assert_eq!(b.custom_payload(), Some(&payload));
+            assert_eq!(a, b);
+        } else {
+            assert_eq!(b.custom_payload(), None);
+        }
+    }
+}
+
+#[test]
+fn response_custom_payload() {
+    let v = vec![
+        0x84, 0x04, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x0A,
+        0x00, 0x01, 0x00, 0x01, b'a', 0x00, 0x00, 0x00, 0x01, 0x07,
+    ];
+    let mut frame = Frame::new(Version::V4, 1, Cursor::new(v), None);
+
+    let envelope = frame.decode().unwrap();
+    let custom_payload = envelope.custom_payload().clone();
+    let m = envelope.into_message();
+    match m {
+        MessageKind::Ready(_) => {},
+        _ => unreachable!("{:?}", m),
+    }
+    assert_eq!(custom_payload, Some(hashmap!{"a".to_string() => Some(vec![7])}));
+}
+
+#[test]
+fn warnings() {
+    let warning = b"Batch too large";
+    let mut v = vec![0x84, 0x0A, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x27];
+    v.extend_from_slice(&[0x11; 16]);
+    v.extend_from_slice(&[0x00, 0x01, 0x00, warning.len() as u8]);
+    v.extend_from_slice(warning);
+    v.extend_from_slice(&[0x00, 0x00, 0x00, 0x01]);
+    let mut frame = Frame::new(Version::V4, 1, Cursor::new(v), None);
+
+    let envelope = frame.decode().unwrap();
+    assert_eq!(envelope.version(), Version::V4);
+    assert_eq!(envelope.flags(), Flags::Tracing as u8 | Flags::Warning as u8);
+    assert_eq!(envelope.stream_id(), 1);
+    assert_eq!(envelope.opcode(), Opcode::Result);
+    assert_eq!(envelope.tracing_id(), &Some(Uuid::from_bytes([0x11; 16])));
+    assert_eq!(envelope.warnings(), &Some(vec!["Batch too large".to_string()]));
+    assert_eq!(envelope.custom_payload(), &None);
+    assert_eq!(envelope.length(), 0x27);
+
+    let m = match envelope.into_message() {
+        MessageKind::Result(m) => m,
+        m => unreachable!("{:?}", m),
+    };
+    assert_eq!(m.tracing_id(), &Some(Uuid::from_bytes([0x11; 16])));
+}
+
+#[test]
+fn malformed() {
+    let v = vec![0x07, 0x00, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00];
+    let mut frame = Frame::new(Version::V4, 1, Cursor::new(v), None);
+    match frame.decode() {
+        Err(ProtError::UnknownVersion(0x07)) => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let v = vec![0x84, 0x00, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00];
+    let mut frame = Frame::new(Version::V4, 1, Cursor::new(v), None);
+    match frame.decode() {
+        Err(ProtError::UnknownOpcode(0x04)) => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let v = vec![0x84, 0x00, 0x00, 0x01, 0x07, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00];
+    let mut frame = Frame::new(Version::V4, 1, Cursor::new(v), None);
+    match frame.decode() {
+        Err(ProtError::InvalidEnumValue("Consistency", ref s)) => assert_eq!(s, "32"),
+        r => unreachable!("{:?}", r),
+    }
+
+    let v = vec![0x84, 0x00, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00];
+    let mut frame = Frame::new(Version::V4, 1, Cursor::new(v), None);
+    match frame.decode() {
+        Err(ProtError::InvalidLength(2)) => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let v = vec![0x84, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let mut frame = Frame::new(Version::V4, 1, Cursor::new(v), None);
+    match frame.decode() {
+        Err(ProtError::IoErr(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof => {},
+        r => unreachable!("{:?}", r),
+    }
+}
+
+#[test]
+fn limits() {
+    let v = vec![0x84, 0x00, 0x00, 0x01, 0x08, 0x7F, 0xFF, 0xFF, 0xFF];
+    let mut frame = Frame::new(Version::V4, 1, Cursor::new(v), None);
+    match frame.decode() {
+        Err(ProtError::LimitExceeded("frame length", 0x7FFF_FFFF)) => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let v = vec![
+        0x84, 0x00, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x10,
+        0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x7F, 0xFF, 0xFF, 0xFF,
+    ];
+    let mut frame = Frame::new(Version::V4, 1, Cursor::new(v), None);
+    match frame.decode() {
+        Err(ProtError::LimitExceeded("collection count", 0x7FFF_FFFF)) => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let mut frame = Frame::new(Version::V4, 1, Cursor::new(Vec::new()), None);
+    frame.encode(&Query::from("SELECT * FROM a")).unwrap();
+    frame.io_mut().set_position(0);
+    frame.limits_mut().set_max_string_length(8);
+    match frame.decode() {
+        Err(ProtError::LimitExceeded("string length", 15)) => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let v = vec![0x84, 0x00, 0x00, 0x01, 0x07, 0x00, 0x00, 0x00, 0x04, 0xFF, 0xFF, 0xFF, 0xFE];
+    let mut frame = Frame::new(Version::V4, 1, Cursor::new(v), None);
+    match frame.decode() {
+        Err(ProtError::InvalidLength(-2)) => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let mut codec = Codec::new(Cursor::new(vec![0x7F, 0xFF, 0xFF, 0xFF, 0x00]));
+    match codec.read_bytes() {
+        Err(ProtError::LimitExceeded("bytes length", 0x7FFF_FFFF)) => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let mut codec = Codec::new(Cursor::new(vec![0x00, 0x00, 0x00, 0x08, 0x00]));
+    assert!(codec.read_bytes().is_err());
+
+    let mut v = [0x00, 0x20].repeat(100_000);
+    v.extend_from_slice(&[0x00, 0x09]);
+    let mut codec = Codec::new(Cursor::new(v));
+    match codec.read_option() {
+        Err(ProtError::LimitExceeded("nesting depth", 65)) => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let mut codec = Codec::new(Cursor::new(vec![0x00, 0x20, 0x00, 0x20, 0x00, 0x09]));
+    codec.limits_mut().set_max_nesting_depth(1);
+    match codec.read_option() {
+        Err(ProtError::LimitExceeded("nesting depth", 2)) => {},
+        r => unreachable!("{:?}", r),
+    }
+    codec.io().set_position(2);
+    assert!(codec.read_option().is_ok());
+}
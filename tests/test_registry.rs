@@ -0,0 +1,69 @@
+use cql::registry::{self, CustomCodec};
+use cql::response::result::*;
+use cql::result::*;
+use cql::types::*;
+
+use std::sync::Arc;
+
+const REVERSED: &str = "com.example.ReversedType";
+const UNKNOWN: &str = "com.example.UnknownType";
+const UNKNOWN_ROWS: &str = "com.example.UnknownRowsType";
+
+struct Reversed;
+
+impl CustomCodec for Reversed {
+    fn marshal(&self, _class_name: &str, v: &DataTypes) -> ProtResult<Bytes> {
+        match v {
+            DataTypes::Varchar(ref v) => marshal_varchar(&v.chars().rev().collect::<String>()),
+            _ => Err(ProtError::TypeMismatch("Reversed", format!("{:?}", v))),
+        }
+    }
+
+    fn unmarshal(&self, _class_name: &str, bytes: &[u8]) -> ProtResult<DataTypes> {
+        let v = unmarshal_varchar(&bytes.to_vec())?;
+        Ok(DataTypes::Varchar(v.chars().rev().collect()))
+    }
+}
+
+fn custom(class_name: &str) -> Opt {
+    Opt {
+        id: OptIds::Custom,
+        value: OptValue::Custom(class_name.to_string()),
+    }
+}
+
+#[test]
+fn unknown() {
+    let v = unmarshal(&custom(UNKNOWN), &vec![1, 2]).unwrap();
+    assert_eq!(v, DataTypes::Custom(Custom::new(UNKNOWN, DataTypes::Blob(vec![1, 2]))));
+    assert_eq!(marshal(&v).unwrap(), Some(vec![1, 2]));
+}
+
+#[test]
+fn codec() {
+    assert!(registry::register(REVERSED, Arc::new(Reversed)).is_none());
+
+    let a = DataTypes::Custom(Custom::new(REVERSED, DataTypes::Varchar("abc".to_string())));
+    let v = marshal(&a).unwrap();
+    assert_eq!(v, Some(b"cba".to_vec()));
+    assert_eq!(unmarshal(&custom(REVERSED), v.as_ref().unwrap()).unwrap(), a);
+
+    let class_name = format!("{}(3)", REVERSED);
+    let b = unmarshal(&custom(&class_name), v.as_ref().unwrap()).unwrap();
+    assert_eq!(b, DataTypes::Custom(Custom::new(&class_name, DataTypes::Varchar("abc".to_string()))));
+
+    let a = DataTypes::Custom(Custom::new(REVERSED, DataTypes::Int(1)));
+    assert!(marshal(&a).is_err());
+
+    assert!(registry::unregister(REVERSED).is_some());
+    assert!(registry::codec(REVERSED).is_none());
+}
+
+#[test]
+fn rows_debug() {
+    let mut metadata = RowsMetadata::default();
+    metadata.set_global_table_spec(GlobalTableSpec::new("ks", "table"));
+    metadata.set_col_specs(vec![ColSpec::new("a", custom(UNKNOWN_ROWS))]);
+    let rows = Rows::new(metadata, vec![vec![Some(vec![1, 2])]]);
+    assert!(format!("{:?}", rows).contains("\n[[1, 2]]\n"));
+}
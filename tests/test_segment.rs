@@ -0,0 +1,249 @@
+mod fake_snappy;
+
+use cql::def::Version;
+use cql::frame::Frame;
+use cql::message::{Message, MessageKind};
+use cql::request::Query;
+use cql::response::Ready;
+use cql::compression::Compression;
+use cql::segment::{Segment, SegmentCodec, MAX_PAYLOAD_LENGTH};
+
+use strum::IntoEnumIterator;
+
+use std::io::Cursor;
+
+const ABC: [u8; 13] = [0x03, 0x00, 0x02, 0x42, 0x96, 0x7C, 0x61, 0x62, 0x63, 0x08, 0x7A, 0x36, 0xC5];
+const ABC_NOT_SELF_CONTAINED: [u8; 13] = [0x03, 0x00, 0x00, 0x5F, 0x47, 0xC5, 0x61, 0x62, 0x63, 0x08, 0x7A, 0x36, 0xC5];
+const ABC_UNCOMPRESSED: [u8; 15] = [
+    0x03, 0x00, 0x00, 0x00, 0x04, 0x77, 0x22, 0x83, 0x61, 0x62, 0x63, 0x08, 0x7A, 0x36, 0xC5,
+];
+
+const READY_ENVELOPE: [u8; 9] = [0x85, 0x10, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00];
+const READY_SEGMENT: [u8; 19] = [
+    0x09, 0x00, 0x02, 0xA4, 0xC8, 0xC1, 0x85, 0x10, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0xC1, 0xB8, 0xBA, 0x2E,
+];
+const READY_SPLIT_HEAD: [u8; 15] = [
+    0x05, 0x00, 0x00, 0x04, 0x48, 0x23, 0x85, 0x10, 0x00, 0x01, 0x02, 0xFF, 0xE9, 0x36, 0x98,
+];
+const READY_SPLIT_TAIL: [u8; 14] = [0x04, 0x00, 0x00, 0x1C, 0x28, 0x4B, 0x00, 0x00, 0x00, 0x00, 0x89, 0xE6, 0x05, 0x5A];
+
+fn new_frame(io: Cursor<Vec<u8>>, compressed: bool) -> Frame<Cursor<Vec<u8>>> {
+    let compressor = if compressed { Compression::Snappy.compressor() } else { None };
+    Frame::new(Version::V5, 0, io, compressor)
+}
+
+fn assert_ready(m: MessageKind) {
+    match m {
+        MessageKind::Ready(_) => {},
+        _ => unreachable!("{:?}", m),
+    }
+}
+
+#[test]
+fn encode() {
+    let mut codec = SegmentCodec::new(None);
+
+    let mut v = Vec::new();
+    codec.encode(&mut v, &Segment::new(b"abc".to_vec(), true)).unwrap();
+    assert_eq!(v, ABC);
+
+    let mut v = Vec::new();
+    codec.encode(&mut v, &Segment::new(b"abc".to_vec(), false)).unwrap();
+    assert_eq!(v, ABC_NOT_SELF_CONTAINED);
+}
+
+#[test]
+fn decode() {
+    let mut codec = SegmentCodec::new(None);
+
+    let segment = codec.decode(&mut Cursor::new(ABC)).unwrap();
+    assert_eq!(segment.payload(), b"abc");
+    assert!(segment.is_self_contained());
+
+    let segment = codec.decode(&mut Cursor::new(ABC_NOT_SELF_CONTAINED)).unwrap();
+    assert_eq!(segment.payload(), b"abc");
+    assert!(!segment.is_self_contained());
+}
+
+#[test]
+fn crc_mismatch() {
+    let mut codec = SegmentCodec::new(None);
+
+    let mut v = ABC;
+    v[4] ^= 0x01;
+    assert!(codec.decode(&mut Cursor::new(v)).is_err());
+
+    let mut v = ABC;
+    v[7] ^= 0x01;
+    assert!(codec.decode(&mut Cursor::new(v)).is_err());
+
+    let mut v = ABC;
+    v[12] ^= 0x01;
+    assert!(codec.decode(&mut Cursor::new(v)).is_err());
+}
+
+#[test]
+fn compressed() {
+    let mut codec = SegmentCodec::new(Some(Box::new(fake_snappy::Snappy::new())));
+
+    let mut v = Vec::new();
+    codec.encode(&mut v, &Segment::new(b"abc".to_vec(), true)).unwrap();
+    assert_eq!(v, ABC_UNCOMPRESSED);
+
+    let segment = codec.decode(&mut Cursor::new(v)).unwrap();
+    assert_eq!(segment, Segment::new(b"abc".to_vec(), true));
+}
+
+#[cfg(feature = "snappy")]
+#[test]
+fn compressed_snappy() {
+    let mut codec = SegmentCodec::new(Compression::Snappy.compressor());
+
+    let payload = vec![b'a'; 1024];
+    let mut v = Vec::new();
+    codec.encode(&mut v, &Segment::new(payload.clone(), true)).unwrap();
+    assert!(v.len() < payload.len());
+
+    let segment = codec.decode(&mut Cursor::new(v)).unwrap();
+    assert_eq!(segment, Segment::new(payload, true));
+}
+
+#[test]
+fn frame_encode() {
+    let mut frame = Frame::new(Version::V5, 1, Cursor::new(Vec::new()), None);
+    assert!(!frame.is_segmented());
+
+    frame.encode(&Ready::new()).unwrap();
+    assert!(frame.is_segmented());
+    frame.encode(&Ready::new()).unwrap();
+
+    let v = frame.io_mut().get_ref();
+    assert_eq!(&v[..READY_ENVELOPE.len()], READY_ENVELOPE);
+    assert_eq!(&v[READY_ENVELOPE.len()..], READY_SEGMENT);
+}
+
+#[test]
+fn frame_decode() {
+    let mut v = READY_ENVELOPE.to_vec();
+    v.extend_from_slice(&READY_SEGMENT);
+    let mut frame = Frame::new(Version::V5, 1, Cursor::new(v), None);
+
+    let envelope = frame.decode().unwrap();
+    let stream_id = envelope.stream_id();
+    let m = envelope.into_message();
+    assert_eq!(stream_id, 1);
+    assert_ready(m);
+    assert!(frame.is_segmented());
+
+    let envelope = frame.decode().unwrap();
+    let stream_id = envelope.stream_id();
+    let m = envelope.into_message();
+    assert_eq!(stream_id, 1);
+    assert_ready(m);
+}
+
+#[test]
+fn frame_serde() {
+    for version in Version::iter() {
+        let mut frame = Frame::new(version, 0, Cursor::new(Vec::new()), Compression::Snappy.compressor());
+        frame.encode(&Ready::new()).unwrap();
+        assert_eq!(frame.is_segmented(), version >= Version::V5);
+
+        let a = Query::from("SELECT * FROM a");
+        frame.encode(&a).unwrap();
+
+        let v = frame.io_mut().get_ref().clone();
+        let mut frame = Frame::new(version, 0, Cursor::new(v), Compression::Snappy.compressor());
+        let m = frame.decode().unwrap().into_message();
+        assert_ready(m);
+
+        let m = frame.decode().unwrap().into_message();
+        let b = match m {
+            MessageKind::Query(b) => b,
+            _ => unreachable!("{:?}", m),
+        };
+        assert_eq!(a, b);
+    }
+}
+
+#[test]
+fn split() {
+    let mut frame = Frame::new(Version::V5, 1, Cursor::new(Vec::new()), None);
+    frame.set_max_payload_length(5);
+    frame.encode(&Ready::new()).unwrap();
+    frame.encode(&Ready::new()).unwrap();
+
+    let mut v = READY_ENVELOPE.to_vec();
+    v.extend_from_slice(&READY_SPLIT_HEAD);
+    v.extend_from_slice(&READY_SPLIT_TAIL);
+    assert_eq!(frame.io_mut().get_ref(), &v);
+}
+
+#[test]
+fn reassemble() {
+    let mut v = READY_ENVELOPE.to_vec();
+    v.extend_from_slice(&READY_SPLIT_HEAD);
+    v.extend_from_slice(&READY_SPLIT_TAIL);
+    v.extend_from_slice(&READY_SEGMENT);
+    let mut frame = Frame::new(Version::V5, 1, Cursor::new(v), None);
+
+    for _ in 0..3 {
+        let envelope = frame.decode().unwrap();
+        let stream_id = envelope.stream_id();
+        let m = envelope.into_message();
+        assert_eq!(stream_id, 1);
+        assert_ready(m);
+    }
+}
+
+#[test]
+fn reassemble_invalid() {
+    let mut v = READY_ENVELOPE.to_vec();
+    v.extend_from_slice(&READY_SPLIT_HEAD);
+    v.extend_from_slice(&READY_SEGMENT);
+    let mut frame = Frame::new(Version::V5, 1, Cursor::new(v), None);
+    frame.decode().unwrap();
+    assert!(frame.decode().is_err());
+
+    let mut v = READY_ENVELOPE.to_vec();
+    v.extend_from_slice(&READY_SPLIT_HEAD);
+    v.extend_from_slice(&READY_SPLIT_TAIL);
+    let mut frame = Frame::new(Version::V5, 1, Cursor::new(v), None);
+    frame.set_max_message_length(8);
+    frame.decode().unwrap();
+    assert!(frame.decode().is_err());
+}
+
+#[test]
+fn large_message() {
+    for compressed in [false, true] {
+        let mut frame = new_frame(Cursor::new(Vec::new()), compressed);
+        frame.encode(&Ready::new()).unwrap();
+
+        let s: String = (0..MAX_PAYLOAD_LENGTH * 2).map(|i| (b'a' + (i * 7 % 26) as u8) as char).collect();
+        let a = Query::from(s.as_str());
+        frame.encode(&a).unwrap();
+
+        let v = frame.io_mut().get_ref().clone();
+        let mut frame = new_frame(Cursor::new(v), compressed);
+        let m = frame.decode().unwrap().into_message();
+        assert_ready(m);
+
+        let m = frame.decode().unwrap().into_message();
+        let b = match m {
+            MessageKind::Query(b) => b,
+            _ => unreachable!("{:?}", m),
+        };
+        assert_eq!(a, b);
+    }
+}
+
+#[test]
+fn large_message_limit() {
+    let mut frame = Frame::new(Version::V5, 0, Cursor::new(Vec::new()), None);
+    frame.set_max_message_length(1024);
+    frame.encode(&Ready::new()).unwrap();
+
+    let s = "a".repeat(1024);
+    assert!(frame.encode(&Query::from(s.as_str())).is_err());
+}
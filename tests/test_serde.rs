@@ -0,0 +1,195 @@
+#![cfg(feature = "serde")]
+
+#[macro_use]
+extern crate maplit;
+
+use cql::de;
+use cql::response::result::ColSpec;
+use cql::result::ProtError;
+use cql::ser;
+use cql::types::*;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Config {
+    name: String,
+    retries: i32,
+    tags: Vec<String>,
+    timeout: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+enum Level {
+    Low,
+    High,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Row {
+    id: i32,
+    config: Config,
+    level: Level,
+    extra: HashMap<String, i64>,
+}
+
+fn list(id: OptIds) -> Opt {
+    Opt {
+        id: OptIds::List,
+        value: OptValue::List(Box::new(Opt::new(id))),
+    }
+}
+
+fn config_type() -> Opt {
+    Opt {
+        id: OptIds::Udt,
+        value: OptValue::Udt(OptUdt {
+            ks: "ks".to_string(),
+            name: "config".to_string(),
+            fields: vec![
+                ("name".to_string(), Opt::new(OptIds::Varchar)),
+                ("retries".to_string(), Opt::new(OptIds::Int)),
+                ("tags".to_string(), list(OptIds::Varchar)),
+                ("timeout".to_string(), Opt::new(OptIds::Double)),
+            ],
+        }),
+    }
+}
+
+fn map_type() -> Opt {
+    Opt {
+        id: OptIds::Map,
+        value: OptValue::Map(Box::new(Opt::new(OptIds::Varchar)), Box::new(Opt::new(OptIds::Bigint))),
+    }
+}
+
+fn config() -> Config {
+    Config {
+        name: "a".to_string(),
+        retries: 3,
+        tags: vec!["b".to_string(), "c".to_string()],
+        timeout: None,
+    }
+}
+
+fn into_bytes(v: Value) -> Bytes {
+    match v {
+        Value::Some(v) => Some(v),
+        _ => None,
+    }
+}
+
+#[test]
+fn udt() {
+    let ty = config_type();
+    let a = config();
+    let bytes = into_bytes(ser::to_value(&a, &ty).unwrap());
+
+    let v = unmarshal(&ty, bytes.as_ref().unwrap()).unwrap();
+    assert_eq!(v, DataTypes::Udt(Udt::new("ks", "config", vec![
+        ("name".to_string(), DataTypes::Varchar("a".to_string())),
+        ("retries".to_string(), DataTypes::Int(3)),
+        ("tags".to_string(), DataTypes::List(vec![
+            DataTypes::Varchar("b".to_string()),
+            DataTypes::Varchar("c".to_string()),
+        ])),
+        ("timeout".to_string(), DataTypes::Null),
+    ])));
+    assert_eq!(de::from_data_types::<Config>(&ty, &v).unwrap(), a);
+    assert_eq!(de::from_bytes::<Config>(&ty, &bytes).unwrap(), a);
+}
+
+#[test]
+fn map() {
+    let ty = map_type();
+    let a = hashmap! {"a".to_string() => 1i64, "b".to_string() => 2};
+    let bytes = into_bytes(ser::to_value(&a, &ty).unwrap());
+    assert_eq!(de::from_bytes::<HashMap<String, i64>>(&ty, &bytes).unwrap(), a);
+}
+
+#[test]
+fn row() {
+    let col_specs = vec![
+        ColSpec::new("id", Opt::new(OptIds::Int)),
+        ColSpec::new("config", config_type()),
+        ColSpec::new("level", Opt::new(OptIds::Varchar)),
+        ColSpec::new("extra", map_type()),
+    ];
+    let row = vec![
+        marshal_int(&1).unwrap(),
+        into_bytes(ser::to_value(&config(), col_specs[1].ty()).unwrap()),
+        marshal_varchar("High").unwrap(),
+        into_bytes(ser::to_value(&hashmap! {"a" => 1}, col_specs[3].ty()).unwrap()),
+    ];
+
+    let a: Row = de::from_row(&col_specs, &row).unwrap();
+    assert_eq!(a, Row {
+        id: 1,
+        config: config(),
+        level: Level::High,
+        extra: hashmap! {"a".to_string() => 1},
+    });
+}
+
+#[test]
+fn vector() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Embedding {
+        id: i32,
+        points: Vec<(i32, String)>,
+    }
+
+    let ty = Opt::custom(
+        "org.apache.cassandra.db.marshal.VectorType(org.apache.cassandra.db.marshal.TupleType(\
+         org.apache.cassandra.db.marshal.Int32Type,org.apache.cassandra.db.marshal.UTF8Type), 2)",
+    );
+    let v = DataTypes::Vector(vec![
+        DataTypes::Tuple(vec![DataTypes::Int(1), DataTypes::Varchar("a".to_string())]),
+        DataTypes::Tuple(vec![DataTypes::Int(2), DataTypes::Varchar("b".to_string())]),
+    ]);
+    let col_specs = vec![ColSpec::new("id", Opt::new(OptIds::Int)), ColSpec::new("points", ty)];
+    let row = vec![marshal_int(&1).unwrap(), marshal(&v).unwrap()];
+
+    let a: Embedding = de::from_row(&col_specs, &row).unwrap();
+    assert_eq!(a, Embedding {
+        id: 1,
+        points: vec![(1, "a".to_string()), (2, "b".to_string())],
+    });
+}
+
+#[test]
+fn scalar() {
+    let ty = Opt::new(OptIds::Timestamp);
+    let bytes = into_bytes(ser::to_value("2020-01-02T03:04:05+00:00", &ty).unwrap());
+    assert_eq!(bytes, marshal_timestamp(&"2020-01-02T03:04:05Z".parse().unwrap()).unwrap());
+    assert_eq!(de::from_bytes::<String>(&ty, &bytes).unwrap(), "2020-01-02T03:04:05+00:00");
+    assert_eq!(into_bytes(ser::to_value(&0i64, &ty).unwrap()), marshal_bigint(&0).unwrap());
+    match ser::to_value(&i64::MAX, &ty) {
+        Err(ProtError::SerdeErr(_)) => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let ty = Opt::new(OptIds::Smallint);
+    assert_eq!(into_bytes(ser::to_value(&1u8, &ty).unwrap()), marshal_smallint(&1).unwrap());
+    assert_eq!(ser::to_value(&None::<i32>, &ty).unwrap(), Value::None);
+    assert!(ser::to_value(&100_000, &ty).is_err());
+}
+
+#[test]
+fn mismatch() {
+    match ser::to_value("a", &Opt::new(OptIds::Int)) {
+        Err(ProtError::TypeMismatch(..)) => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    let mut a = hashmap! {"name" => "a"};
+    a.insert("other", "b");
+    match ser::to_value(&a, &config_type()) {
+        Err(ProtError::UnknownField(ref name)) if name == "other" => {},
+        r => unreachable!("{:?}", r),
+    }
+
+    assert!(de::from_data_types::<i32>(&Opt::new(OptIds::Varchar), &DataTypes::Varchar("a".to_string())).is_err());
+}
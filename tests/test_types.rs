@@ -11,6 +11,7 @@ use num::BigInt;
 use uuid::{Uuid, v1::Timestamp};
 
 use bigdecimal::BigDecimal;
+use std::collections::{HashMap, HashSet};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
 #[test]
@@ -61,6 +62,12 @@ fn string_multimap() {
     assert_eq!(t.length(), 13);
 }
 
+#[test]
+fn bytes_map() {
+    let t = hashmap!{"a".to_string() => Some(vec![1, 2]), "b".to_string() => None};
+    assert_eq!(t.length(), 18);
+}
+
 #[test]
 fn option() {
     let t = Some("a".to_string());
@@ -109,6 +116,7 @@ macro_rules! test_marshal {
 
 mod data_types {
     use super::*;
+    use cql::result::ProtError;
 
     #[test]
     fn ascii() {
@@ -170,6 +178,7 @@ mod data_types {
     fn timestamp() {
         let a = Utc.timestamp_millis(Utc::now().timestamp_millis());
         test_marshal!(a, Timestamp, timestamp);
+        assert!(unmarshal_timestamp(&std::i64::MAX.to_be_bytes().to_vec()).is_err());
     }
 
     #[test]
@@ -209,12 +218,19 @@ mod data_types {
     fn date() {
         let a = Utc::today();
         test_marshal!(a, Date, date);
+
+        let a = Utc.ymd(1969, 12, 31);
+        assert_eq!(unmarshal_date(&marshal_date(&a).unwrap().unwrap()).unwrap(), a);
+        assert_eq!(unmarshal_date(&vec![0x7F, 0xFF, 0xFF, 0xFF]).unwrap(), a);
+        assert!(unmarshal_date(&vec![0x00, 0x00, 0x00, 0x00]).is_err());
     }
 
     #[test]
     fn time() {
         let a = Utc::now().time();
         test_marshal!(a, Time, time);
+        assert!(unmarshal_time(&(-1i64).to_be_bytes().to_vec()).is_err());
+        assert!(unmarshal_time(&86_400_000_000_000i64.to_be_bytes().to_vec()).is_err());
     }
 
     #[test]
@@ -241,39 +257,146 @@ mod data_types {
         test_marshal!(a, List, Int, list);
     }
 
+    #[test]
+    fn marshal_null() {
+        assert_eq!(marshal(&DataTypes::Null).unwrap(), None);
+
+        let a = vec![DataTypes::Int(0), DataTypes::Null];
+        let v = marshal(&DataTypes::List(a)).unwrap();
+        assert_eq!(v, Some(vec![0, 0, 0, 2, 0, 0, 0, 4, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]));
+        assert_eq!(Value::from(marshal(&DataTypes::Null).unwrap()), Value::None);
+    }
+
+    #[test]
+    fn marshal_nested() {
+        let a = DataTypes::Map(vec![
+            (DataTypes::Varchar("a".to_string()), DataTypes::List(vec![DataTypes::Int(1), DataTypes::Null])),
+            (DataTypes::Varchar("b".to_string()), DataTypes::List(vec![])),
+        ]);
+        let v = marshal(&a).unwrap();
+        let ty = Opt {
+            id: OptIds::Map,
+            value: OptValue::Map(Box::new(Opt::new(OptIds::Varchar)), Box::new(Opt {
+                id: OptIds::List,
+                value: OptValue::List(Box::new(Opt::new(OptIds::Int))),
+            })),
+        };
+        assert_eq!(unmarshal(&ty, v.as_ref().unwrap()).unwrap(), a);
+
+        let a = DataTypes::Tuple(vec![DataTypes::Set(vec![DataTypes::Int(1)]), DataTypes::Null]);
+        let v = marshal(&a).unwrap();
+        let ty = Opt {
+            id: OptIds::Tuple,
+            value: OptValue::Tuple(vec![
+                Opt {
+                    id: OptIds::Set,
+                    value: OptValue::Set(Box::new(Opt::new(OptIds::Int))),
+                },
+                Opt::new(OptIds::Varchar),
+            ]),
+        };
+        assert_eq!(unmarshal(&ty, v.as_ref().unwrap()).unwrap(), a);
+    }
+
     #[test]
     fn map() {
-        let a = hashmap!{DataTypes::Varchar("a".to_string()) => DataTypes::Int(1),
-                         DataTypes::Varchar("b".to_string()) => DataTypes::Int(2)};
+        let a = vec![(DataTypes::Varchar("a".to_string()), DataTypes::Int(1)),
+                     (DataTypes::Varchar("b".to_string()), DataTypes::Int(2))];
         test_marshal!(a, Map, Varchar, Int, map);
     }
 
+    #[test]
+    fn map_order() {
+        let a = vec![(DataTypes::Varchar("b".to_string()), DataTypes::Int(2)),
+                     (DataTypes::Varchar("a".to_string()), DataTypes::Int(1))];
+        let v = marshal_map(&a).unwrap();
+        assert_eq!(v, Some(vec![
+            0, 0, 0, 2,
+            0, 0, 0, 1, b'b', 0, 0, 0, 4, 0, 0, 0, 2,
+            0, 0, 0, 1, b'a', 0, 0, 0, 4, 0, 0, 0, 1,
+        ]));
+        assert_eq!(marshal_map(&a).unwrap(), v);
+
+        let ty = Opt {
+            id: OptIds::Map,
+            value: OptValue::Map(Box::new(Opt::new(OptIds::Varchar)), Box::new(Opt::new(OptIds::Int))),
+        };
+        let b = unmarshal(&ty, v.as_ref().unwrap()).unwrap();
+        assert_eq!(b, DataTypes::Map(a));
+        assert_eq!(format!("{:?}", b), "{b: 2, a: 1}");
+    }
+
     #[test]
     fn set() {
-        let a = hashset![DataTypes::Float(0.1), DataTypes::Float(1.2), DataTypes::Float(2.3)];
+        let a = vec![DataTypes::Float(0.1), DataTypes::Float(1.2), DataTypes::Float(2.3)];
         test_marshal!(a, Set, Float, set);
 
-        let a = hashset![DataTypes::Double(0.1), DataTypes::Double(1.2), DataTypes::Double(2.3)];
+        let a = vec![DataTypes::Double(0.1), DataTypes::Double(1.2), DataTypes::Double(2.3)];
         test_marshal!(a, Set, Double, set);
+
+        let v = marshal_set(&vec![DataTypes::Int(2), DataTypes::Int(1)]).unwrap();
+        assert_eq!(v, Some(vec![0, 0, 0, 2, 0, 0, 0, 4, 0, 0, 0, 2, 0, 0, 0, 4, 0, 0, 0, 1]));
+    }
+
+    fn udt_type() -> OptUdt {
+        OptUdt {
+            ks: "ks".to_string(),
+            name: "udt".to_string(),
+            fields: vec![
+                ("a".to_string(), Opt::new(OptIds::Boolean)),
+                ("b".to_string(), Opt::new(OptIds::Double)),
+                ("c".to_string(), Opt::new(OptIds::Varchar)),
+            ],
+        }
     }
 
     #[test]
     fn udt() {
-        let a = vec![DataTypes::Boolean(true), DataTypes::Double(0.1), DataTypes::Varchar("a".to_string())];
+        let a = Udt::new("ks", "udt", vec![
+            ("a".to_string(), DataTypes::Boolean(true)),
+            ("b".to_string(), DataTypes::Double(0.1)),
+            ("c".to_string(), DataTypes::Varchar("a".to_string())),
+        ]);
         let v = marshal_udt(&a).unwrap();
         let ty = Opt {
             id: OptIds::Udt,
-            value: OptValue::Udt(OptUdt::from_types(vec![Opt::new(OptIds::Boolean),
-                    Opt::new(OptIds::Double),
-                    Opt::new(OptIds::Varchar)])),
+            value: OptValue::Udt(udt_type()),
         };
         if let DataTypes::Udt(b) = unmarshal_complex(&ty, v.as_ref().unwrap()).unwrap() {
+            assert_eq!(b.get("b"), Some(&DataTypes::Double(0.1)));
+            assert_eq!(format!("{:?}", b), "ks.udt {a: true, b: 0.1, c: a}");
             assert_eq!(a, b);
         } else {
             unreachable!();
         }
     }
 
+    #[test]
+    fn udt_missing_fields() {
+        let a = Udt::new("ks", "udt", vec![("a".to_string(), DataTypes::Boolean(true))]);
+        let v = marshal_udt(&a).unwrap();
+        let b = unmarshal_udt(&udt_type(), v.as_ref().unwrap()).unwrap();
+        assert_eq!(b.fields.len(), 3);
+        assert_eq!(b.get("a"), Some(&DataTypes::Boolean(true)));
+        assert_eq!(b.get("c"), Some(&DataTypes::Null));
+    }
+
+    #[test]
+    fn udt_as() {
+        let a = Udt::new("ks", "udt", vec![
+            ("c".to_string(), DataTypes::Varchar("a".to_string())),
+            ("a".to_string(), DataTypes::Boolean(true)),
+        ]);
+        let v = marshal_udt_as(&a, &udt_type()).unwrap();
+        assert_eq!(v, Some(vec![0, 0, 0, 1, 1, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 1, b'a']));
+
+        let a = Udt::new("ks", "udt", vec![("d".to_string(), DataTypes::Boolean(true))]);
+        match marshal_udt_as(&a, &udt_type()) {
+            Err(ProtError::UnknownField(ref name)) if name == "d" => {},
+            r => unreachable!("{:?}", r),
+        }
+    }
+
     #[test]
     fn tuple() {
         let a = vec![DataTypes::Boolean(true), DataTypes::Double(0.1), DataTypes::Varchar("a".to_string())];
@@ -292,3 +415,346 @@ mod data_types {
     }
 }
 
+
+mod to_from_cql {
+    use super::*;
+    use cql::result::ProtError;
+
+    fn list(id: OptIds) -> Opt {
+        Opt {
+            id: OptIds::List,
+            value: OptValue::List(Box::new(Opt::new(id))),
+        }
+    }
+
+    fn serde<T: ToCql + FromCql>(a: &T, ty: &Opt) -> T {
+        let v = match a.to_cql(ty).unwrap() {
+            Value::Some(v) => Some(v),
+            Value::None => None,
+            Value::NotSet => unreachable!(),
+        };
+        T::from_cql(ty, &v).unwrap()
+    }
+
+    #[test]
+    fn primitive() {
+        assert_eq!(serde(&1i8, &Opt::new(OptIds::Tinyint)), 1);
+        assert_eq!(serde(&1i16, &Opt::new(OptIds::Smallint)), 1);
+        assert_eq!(serde(&1i32, &Opt::new(OptIds::Int)), 1);
+        assert_eq!(serde(&1i64, &Opt::new(OptIds::Bigint)), 1);
+        assert_eq!(serde(&1i64, &Opt::new(OptIds::Counter)), 1);
+        assert_eq!(serde(&0.1f32, &Opt::new(OptIds::Float)), 0.1);
+        assert_eq!(serde(&0.1f64, &Opt::new(OptIds::Double)), 0.1);
+        assert!(serde(&true, &Opt::new(OptIds::Boolean)));
+        assert_eq!(serde(&"a".to_string(), &Opt::new(OptIds::Varchar)), "a");
+        assert_eq!(serde(&vec![1u8, 2], &Opt::new(OptIds::Blob)), vec![1, 2]);
+
+        assert_eq!(1i32.to_cql(&Opt::new(OptIds::Int)).unwrap(), Value::Some(vec![0, 0, 0, 1]));
+        assert_eq!("a".to_cql(&Opt::new(OptIds::Ascii)).unwrap(), Value::Some(vec![b'a']));
+    }
+
+    #[test]
+    fn other() {
+        let a = BigDecimal::parse_bytes(b"1.23", 10).unwrap();
+        assert_eq!(serde(&a, &Opt::new(OptIds::Decimal)), a);
+        let a = BigInt::from(-123);
+        assert_eq!(serde(&a, &Opt::new(OptIds::Varint)), a);
+        let a = Utc.timestamp_millis(1_500_000_000_000);
+        assert_eq!(serde(&a, &Opt::new(OptIds::Timestamp)), a);
+        let a = Utc.ymd(2020, 1, 2);
+        assert_eq!(serde(&a, &Opt::new(OptIds::Date)), a);
+        let a = NaiveTime::from_hms(1, 2, 3);
+        assert_eq!(serde(&a, &Opt::new(OptIds::Time)), a);
+        let a = Uuid::parse_str("f1e5f3a0-4c4b-11ea-b77f-2e728ce88125").unwrap();
+        assert_eq!(serde(&a, &Opt::new(OptIds::Uuid)), a);
+        assert_eq!(serde(&a, &Opt::new(OptIds::Timeuuid)), a);
+        let a = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        assert_eq!(serde(&a, &Opt::new(OptIds::Inet)), a);
+    }
+
+    #[test]
+    fn option() {
+        let ty = Opt::new(OptIds::Int);
+        assert_eq!(None::<i32>.to_cql(&ty).unwrap(), Value::None);
+        assert_eq!(serde(&Some(1), &ty), Some(1));
+        assert_eq!(Option::<i32>::from_cql(&ty, &None).unwrap(), None);
+
+        match i32::from_cql(&ty, &None) {
+            Err(ProtError::UnexpectedNull(_)) => {},
+            r => unreachable!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn collection() {
+        let a = vec![1, 2, 3];
+        assert_eq!(serde(&a, &list(OptIds::Int)), a);
+
+        let ty = Opt {
+            id: OptIds::Set,
+            value: OptValue::Set(Box::new(Opt::new(OptIds::Varchar))),
+        };
+        let a = hashset! {"a".to_string(), "b".to_string()};
+        assert_eq!(serde(&a, &ty), a);
+
+        let ty = Opt {
+            id: OptIds::Map,
+            value: OptValue::Map(Box::new(Opt::new(OptIds::Varchar)), Box::new(list(OptIds::Int))),
+        };
+        let a = hashmap! {"a".to_string() => vec![1], "b".to_string() => vec![2, 3]};
+        assert_eq!(serde(&a, &ty), a);
+    }
+
+    #[test]
+    fn hash_order() {
+        let ty = Opt {
+            id: OptIds::Set,
+            value: OptValue::Set(Box::new(Opt::new(OptIds::Int))),
+        };
+        let a: HashSet<i32> = (0..64).rev().collect();
+        let b: Vec<i32> = (0..64).collect();
+        assert_eq!(a.to_cql(&ty).unwrap(), b.to_cql(&ty).unwrap());
+
+        let ty = Opt {
+            id: OptIds::Map,
+            value: OptValue::Map(Box::new(Opt::new(OptIds::Int)), Box::new(Opt::new(OptIds::Varchar))),
+        };
+        let a: HashMap<i32, String> = (0..64).rev().map(|i| (i, i.to_string())).collect();
+        let b: Vec<_> = (0..64).map(|i| (DataTypes::Int(i), DataTypes::Varchar(i.to_string()))).collect();
+        let b = marshal_map(&b).unwrap();
+        assert_eq!(a.to_cql(&ty).unwrap(), Value::from(b));
+
+        let a = vec![Some(1), None];
+        assert_eq!(serde(&a, &list(OptIds::Int)), a);
+    }
+
+    #[test]
+    fn tuple() {
+        let ty = Opt {
+            id: OptIds::Tuple,
+            value: OptValue::Tuple(vec![
+                Opt::new(OptIds::Boolean),
+                Opt::new(OptIds::Double),
+                Opt::new(OptIds::Varchar),
+            ]),
+        };
+        let a = (true, 0.1, "a".to_string());
+        assert_eq!(serde(&a, &ty), a);
+        assert!((true, 0.1).to_cql(&ty).is_err());
+    }
+
+    #[test]
+    fn mismatch() {
+        match 1i32.to_cql(&Opt::new(OptIds::Bigint)) {
+            Err(ProtError::TypeMismatch(_, _)) => {},
+            r => unreachable!("{:?}", r),
+        }
+        assert!("白兔".to_cql(&Opt::new(OptIds::Ascii)).is_err());
+        assert!(vec![1].to_cql(&Opt::new(OptIds::Int)).is_err());
+        assert!(String::from_cql(&Opt::new(OptIds::Blob), &Some(vec![])).is_err());
+        assert!(Vec::<i64>::from_cql(&list(OptIds::Int), &Some(vec![0, 0, 0, 0])).is_ok());
+        assert!(Vec::<i64>::from_cql(&list(OptIds::Int), &Some(vec![0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 1])).is_err());
+    }
+}
+
+mod vector {
+    use super::*;
+
+    const FLOAT_VECTOR: &str =
+        "org.apache.cassandra.db.marshal.VectorType(org.apache.cassandra.db.marshal.FloatType, 3)";
+    const FLOAT_BYTES: [u8; 12] = [0x3F, 0x80, 0, 0, 0x40, 0, 0, 0, 0x40, 0x40, 0, 0];
+
+    #[test]
+    fn parse() {
+        let (ty, dimension) = parse_vector_type(FLOAT_VECTOR).unwrap();
+        assert_eq!(ty, Opt::new(OptIds::Float));
+        assert_eq!(dimension, 3);
+
+        let (ty, dimension) = parse_vector_type("VectorType(UTF8Type,2)").unwrap();
+        assert_eq!(ty, Opt::new(OptIds::Varchar));
+        assert_eq!(dimension, 2);
+
+        assert!(parse_vector_type("org.apache.cassandra.db.marshal.ListType(FloatType)").is_none());
+        assert!(parse_vector_type("VectorType(FloatType, a)").is_none());
+
+        let ty = Opt::vector(&Opt::new(OptIds::Float), 3).unwrap();
+        assert_eq!(ty.vector_type(), Some((Opt::new(OptIds::Float), 3)));
+    }
+
+    #[test]
+    fn fixed_length() {
+        let a = vec![DataTypes::Float(1.0), DataTypes::Float(2.0), DataTypes::Float(3.0)];
+        let a = DataTypes::Vector(a);
+        let v = marshal(&a).unwrap();
+        assert_eq!(v, Some(FLOAT_BYTES.to_vec()));
+
+        let ty = Opt::custom(FLOAT_VECTOR);
+        assert_eq!(unmarshal(&ty, v.as_ref().unwrap()).unwrap(), a);
+        assert!(unmarshal(&ty, &FLOAT_BYTES[..8].to_vec()).is_err());
+    }
+
+    #[test]
+    fn variable_length() {
+        let a = vec![DataTypes::Varchar("a".to_string()), DataTypes::Varchar("bc".to_string())];
+        let v = marshal_vector(&a).unwrap();
+        assert_eq!(v, Some(vec![1, b'a', 2, b'b', b'c']));
+
+        let ty = Opt::custom("org.apache.cassandra.db.marshal.VectorType(org.apache.cassandra.db.marshal.UTF8Type, 2)");
+        assert_eq!(unmarshal(&ty, v.as_ref().unwrap()).unwrap(), DataTypes::Vector(a));
+        assert!(unmarshal(&ty, &vec![1, b'a', 3, b'b', b'c']).is_err());
+
+        let ty = Opt::vector(&Opt::new(OptIds::Varchar), 1).unwrap();
+        match unmarshal(&ty, &vec![0xFF; 9]) {
+            Err(cql::result::ProtError::InvalidLength(_)) => {},
+            r => unreachable!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn bind() {
+        let ty = Opt::custom(FLOAT_VECTOR);
+        let a = vec![1.0f32, 2.0, 3.0];
+        assert_eq!(a.to_cql(&ty).unwrap(), Value::Some(FLOAT_BYTES.to_vec()));
+        assert_eq!(Vec::<f32>::from_cql(&ty, &Some(FLOAT_BYTES.to_vec())).unwrap(), a);
+        assert!(vec![1.0f32].to_cql(&ty).is_err());
+        assert!(vec![1.0f64, 2.0, 3.0].to_cql(&ty).is_err());
+    }
+}
+
+mod type_string {
+    use super::*;
+
+    fn parse(s: &str) -> Opt {
+        s.parse().unwrap()
+    }
+
+    fn collection(id: OptIds, ty: Opt) -> Opt {
+        let value = match id {
+            OptIds::List => OptValue::List(Box::new(ty)),
+            _ => OptValue::Set(Box::new(ty)),
+        };
+        Opt {
+            id,
+            value,
+        }
+    }
+
+    fn udt(ks: &str, name: &str, fields: Vec<(String, Opt)>) -> Opt {
+        Opt {
+            id: OptIds::Udt,
+            value: OptValue::Udt(OptUdt {
+                ks: ks.to_string(),
+                name: name.to_string(),
+                fields,
+            }),
+        }
+    }
+
+    #[test]
+    fn simple() {
+        let types = vec![
+            "ascii", "bigint", "blob", "boolean", "counter", "decimal", "double", "float", "int", "timestamp", "uuid",
+            "text", "varint", "timeuuid", "inet", "date", "time", "smallint", "tinyint", "duration",
+        ];
+        for s in types {
+            let ty = parse(s);
+            assert_eq!(ty.value, OptValue::None);
+            assert_eq!(ty.to_string(), s);
+            assert_eq!(parse(&s.to_uppercase()), ty);
+        }
+        assert_eq!(parse("varchar"), Opt::new(OptIds::Varchar));
+    }
+
+    #[test]
+    fn nested() {
+        let s = "map<text, frozen<list<tuple<int, timeuuid>>>>";
+        let tuple = Opt {
+            id: OptIds::Tuple,
+            value: OptValue::Tuple(vec![Opt::new(OptIds::Int), Opt::new(OptIds::Timeuuid)]),
+        };
+        let ty = Opt {
+            id: OptIds::Map,
+            value: OptValue::Map(Box::new(Opt::new(OptIds::Varchar)), Box::new(collection(OptIds::List, tuple))),
+        };
+        assert_eq!(parse(s), ty);
+        assert_eq!(ty.to_string(), s);
+
+        assert_eq!(parse(" set < frozen < set<int> > > ").to_string(), "set<frozen<set<int>>>");
+        assert_eq!(parse("frozen<list<int>>").to_string(), "list<int>");
+    }
+
+    #[test]
+    fn udt_and_custom() {
+        assert_eq!(parse("frozen<ks.addr>"), udt("ks", "addr", Vec::new()));
+        assert_eq!(parse("frozen<ks.addr>").to_string(), "ks.addr");
+        assert_eq!(parse("addr"), udt("", "addr", Vec::new()));
+        assert_eq!(parse("\"Ks\".\"My \"\"Type\"\"\""), udt("Ks", "My \"Type\"", Vec::new()));
+        assert_eq!(parse("list<frozen<ks.addr>>").to_string(), "list<frozen<ks.addr>>");
+        assert_eq!(udt("Ks", "My \"Type\"", Vec::new()).to_string(), "\"Ks\".\"My \"\"Type\"\"\"");
+
+        let ty = parse("'org.apache.cassandra.db.marshal.PointType'");
+        assert_eq!(ty, Opt::custom("org.apache.cassandra.db.marshal.PointType"));
+        assert_eq!(ty.to_string(), "'org.apache.cassandra.db.marshal.PointType'");
+
+        let ty = parse("vector<float, 3>");
+        assert_eq!(ty.vector_type(), Some((Opt::new(OptIds::Float), 3)));
+        assert_eq!(ty.to_string(), "vector<float, 3>");
+    }
+
+    #[test]
+    fn class_name() {
+        let s = "org.apache.cassandra.db.marshal.MapType(org.apache.cassandra.db.marshal.UTF8Type,\
+                 org.apache.cassandra.db.marshal.FrozenType(org.apache.cassandra.db.marshal.ListType(\
+                 org.apache.cassandra.db.marshal.TupleType(org.apache.cassandra.db.marshal.Int32Type,\
+                 org.apache.cassandra.db.marshal.TimeUUIDType))))";
+        assert_eq!(parse(s), parse("map<text, frozen<list<tuple<int, timeuuid>>>>"));
+
+        let s = "org.apache.cassandra.db.marshal.ReversedType(org.apache.cassandra.db.marshal.SetType(\
+                 org.apache.cassandra.db.marshal.TimestampType))";
+        assert_eq!(parse(s), collection(OptIds::Set, Opt::new(OptIds::Timestamp)));
+
+        let s = "org.apache.cassandra.db.marshal.UserType(ks,61646472,\
+                 737472656574:org.apache.cassandra.db.marshal.UTF8Type,\
+                 7a6970:org.apache.cassandra.db.marshal.Int32Type)";
+        let fields = vec![
+            ("street".to_string(), Opt::new(OptIds::Varchar)),
+            ("zip".to_string(), Opt::new(OptIds::Int)),
+        ];
+        assert_eq!(parse(s), udt("ks", "addr", fields));
+        assert_eq!(parse(s).to_string(), "ks.addr");
+
+        let ty = parse_class_name("ListType(org.apache.cassandra.db.marshal.PointType)").unwrap();
+        assert_eq!(ty, collection(OptIds::List, Opt::custom("org.apache.cassandra.db.marshal.PointType")));
+        assert!(parse_class_name("MapType(Int32Type)").is_none());
+        assert!(parse_class_name("UserType(ks,6)").is_none());
+    }
+
+    #[test]
+    fn invalid() {
+        let types = vec![
+            "", "list<int", "map<int>", "list<>", "int>", "vector<float, a>", "vector<list<int>, 2>", "'a",
+        ];
+        for s in types {
+            match s.parse::<Opt>() {
+                Err(cql::result::ProtError::InvalidFormat(..)) => {},
+                r => unreachable!("{:?}", r),
+            }
+        }
+        assert!("org.apache.cassandra.db.marshal.ListType(".parse::<Opt>().is_err());
+    }
+
+    #[test]
+    fn depth() {
+        let s = format!("{}int{}", "list<".repeat(64), ">".repeat(64));
+        assert!(s.parse::<Opt>().is_ok());
+        let s = format!("{}int{}", "list<".repeat(65), ">".repeat(65));
+        assert!(s.parse::<Opt>().is_err());
+        assert!("list<".repeat(100_000).parse::<Opt>().is_err());
+
+        let s = format!("{}Int32Type{}", "ListType(".repeat(64), ")".repeat(64));
+        assert!(parse_class_name(&s).is_some());
+        let s = format!("{}Int32Type{}", "ListType(".repeat(65), ")".repeat(65));
+        assert!(parse_class_name(&s).is_none());
+        assert!(parse_class_name(&"org.apache.cassandra.db.marshal.ListType(".repeat(100_000)).is_none());
+    }
+}